@@ -1,6 +1,6 @@
 'use client'
 
-import { useEffect, useState } from 'react'
+import { useEffect, useRef, useState } from 'react'
 import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
 import { isTauri } from '@/lib/tauri'
@@ -9,6 +9,40 @@ import { Button } from '@/components/ui/button'
 import { Progress } from '@/components/ui/progress'
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from '@/components/ui/select'
 
+type InstalledVersion = {
+  version: string
+  path: string
+  size_bytes: number
+  installed_at: number | null
+  version_output: string | null
+  current: boolean
+  running: boolean
+}
+
+type ReleaseSummary = {
+  tag: string
+  name: string | null
+  published_at: string | null
+  prerelease: boolean
+  platform_asset: string | null
+}
+
+type GcReport = {
+  dry_run: boolean
+  removed: { version: string; channel: string; size_bytes: number }[]
+  freed_bytes: number
+  kept: string[]
+  failed: string[]
+}
+
+type ReleasePage = { page: number; per_page: number; releases: ReleaseSummary[]; has_more: boolean }
+
+function formatSize(bytes: number) {
+  if (bytes >= 1024 * 1024) return `${(bytes / 1024 / 1024).toFixed(1)} MB`
+  if (bytes >= 1024) return `${(bytes / 1024).toFixed(1)} KB`
+  return `${bytes} B`
+}
+
 export default function VersionSwitcher() {
   const [channel, setChannel] = useState<'stable' | 'dev'>('stable')
   const [currentCorePath, setCurrentCorePath] = useState<string>('')
@@ -19,24 +53,61 @@ export default function VersionSwitcher() {
   const [progressStage, setProgressStage] = useState('')
   const [progressError, setProgressError] = useState<string | null>(null)
   const [installDir, setInstallDir] = useState('')
+  const [installed, setInstalled] = useState<InstalledVersion[]>([])
+  const [busyVersion, setBusyVersion] = useState<string | null>(null)
+  const [releases, setReleases] = useState<ReleasePage | null>(null)
+  const [releasesLoading, setReleasesLoading] = useState(false)
+  const [previousVersion, setPreviousVersion] = useState<string | null>(null)
+  const [rollbackNotice, setRollbackNotice] = useState<string | null>(null)
+  const [downloadId, setDownloadId] = useState<string | null>(null)
+  // 当前下载完成后是否将其设为当前内核
+  const makeCurrentRef = useRef(true)
+
+  const loadInstalled = async () => {
+    try {
+      setInstalled(await invoke<InstalledVersion[]>('list_installed_versions'))
+      setPreviousVersion(await invoke<string | null>('get_previous_version'))
+    } catch (e) {
+      console.error(e)
+    }
+  }
 
   useEffect(() => {
     // 直接读取默认路径用于显示（后端启动时已尝试自动采用该路径）
     invoke<string | null>('get_default_core_path').then((d) => { if (d) setCurrentCorePath(d) }).catch(() => {})
     invoke<string>('get_core_install_dir').then(setInstallDir).catch(() => {})
-    ;(async () => {
-      const unlisten = await listen<{ stage: string; progress: number; message?: string }>(
-        'version_install_progress',
-        (e) => {
-          setProgress(e.payload.progress)
-          setProgressStage(e.payload.stage)
-          setProgressError(e.payload.stage === '错误' ? e.payload.message ?? '未知错误' : null)
+    loadInstalled()
+    invoke<string | null>('get_active_download')
+      .then((id) => {
+        if (id) {
+          setDownloadId(id)
+          setInstalling(true)
+        }
+      })
+      .catch(() => {})
+    const unlisten = listen<{ id: string; stage: string; progress: number; message?: string }>(
+      'version_install_progress',
+      (e) => {
+        const { stage, message } = e.payload
+        setProgress(e.payload.progress)
+        setProgressStage(stage === 'cancelled' ? '已取消' : stage)
+        setProgressError(stage === '错误' ? message ?? '未知错误' : null)
+        if (stage === '完成' || stage === '错误' || stage === 'cancelled') {
+          setInstalling(false)
+          setDownloadId(null)
+          if (stage === '完成' && message && makeCurrentRef.current) setCurrentCorePath(message)
+          loadInstalled()
         }
-      )
-      return () => {
-        unlisten()
       }
-    })()
+    )
+    const unlistenRollback = listen<{ from: string; to: string; reason: string }>('core_rolled_back', (e) => {
+      setRollbackNotice(`已从 ${e.payload.from} 回滚到 ${e.payload.to}：${e.payload.reason}`)
+      loadInstalled()
+    })
+    return () => {
+      unlisten.then((f) => f())
+      unlistenRollback.then((f) => f())
+    }
   }, [])
 
   const loadLatest = async () => {
@@ -49,19 +120,101 @@ export default function VersionSwitcher() {
     }
   }
 
-  const downloadAndInstall = async () => {
+  // 下载在后台进行，命令立即返回下载 id，结果通过 version_install_progress 事件获知
+  const beginInstall = async (command: string, args: Record<string, unknown>, makeCurrent: boolean) => {
     setInstalling(true)
     setProgress(0)
     setProgressStage('')
     setProgressError(null)
+    makeCurrentRef.current = makeCurrent
     try {
-      const installedPath = await invoke<string>('download_install_latest', { channel })
-      setCurrentCorePath(installedPath)
+      setDownloadId(await invoke<string>(command, args))
     } catch (e) {
       console.error(e)
-      alert('下载/安装失败，请确认已在 Tauri 桌面端运行，并查看控制台/终端日志')
-    } finally {
       setInstalling(false)
+      alert(`无法开始下载：${e}`)
+    }
+  }
+
+  const downloadAndInstall = () => beginInstall('download_install_latest', { channel }, true)
+
+  const cancelDownload = async () => {
+    if (!downloadId) return
+    try {
+      await invoke('cancel_download', { id: downloadId })
+    } catch (e) {
+      alert(`取消失败：${e}`)
+    }
+  }
+
+  const loadReleases = async (page: number) => {
+    setReleasesLoading(true)
+    try {
+      setReleases(await invoke<ReleasePage>('list_core_releases', { page }))
+    } catch (e) {
+      alert(`获取发布列表失败：${e}`)
+    } finally {
+      setReleasesLoading(false)
+    }
+  }
+
+  const installTag = (tag: string, makeCurrent: boolean) =>
+    beginInstall('download_install_version', { tag, makeCurrent }, makeCurrent)
+
+  const cleanup = async () => {
+    try {
+      const preview = await invoke<GcReport>('gc_core_versions', { dryRun: true })
+      if (preview.removed.length === 0) {
+        alert('没有需要清理的旧版本')
+        return
+      }
+      const names = preview.removed.map((r) => r.version).join('、')
+      if (!confirm(`将删除 ${names}，释放 ${formatSize(preview.freed_bytes)}，是否继续？`)) return
+      const report = await invoke<GcReport>('gc_core_versions', { dryRun: false })
+      if (report.failed.length > 0) alert(`部分版本删除失败：\n${report.failed.join('\n')}`)
+      await loadInstalled()
+    } catch (e) {
+      alert(`清理失败：${e}`)
+    }
+  }
+
+  const rollback = async () => {
+    if (!previousVersion || !confirm(`确定回滚到 ${previousVersion}？`)) return
+    setBusyVersion(previousVersion)
+    try {
+      const path = await invoke<string>('rollback_core_version')
+      setCurrentCorePath(path)
+      await loadInstalled()
+    } catch (e) {
+      alert(`回滚失败：${e}`)
+    } finally {
+      setBusyVersion(null)
+    }
+  }
+
+  const switchVersion = async (version: string) => {
+    setBusyVersion(version)
+    try {
+      const path = await invoke<string>('set_current_version', { version })
+      setCurrentCorePath(path)
+      await loadInstalled()
+    } catch (e) {
+      alert(`切换失败：${e}`)
+    } finally {
+      setBusyVersion(null)
+    }
+  }
+
+  const removeVersion = async (version: string) => {
+    if (!confirm(`确定删除内核版本 ${version}？`)) return
+    setBusyVersion(version)
+    try {
+      await invoke('remove_version', { version })
+      await loadInstalled()
+    } catch (e) {
+      alert(`删除失败：${e}`)
+    } finally {
+      setBusyVersion(null)
     }
   }
 
@@ -110,6 +263,15 @@ export default function VersionSwitcher() {
             >
               {installing ? '安装中…' : '下载并安装'}
             </Button>
+            {installing && downloadId && (
+              <Button
+                variant="outline"
+                onClick={cancelDownload}
+                className="transition-transform active:scale-95 active:translate-y-px"
+              >
+                取消下载
+              </Button>
+            )}
             {!currentCorePath && (
               <Button
                 variant="outline"
@@ -123,7 +285,7 @@ export default function VersionSwitcher() {
               </Button>
             )}
           </div>
-          {(installing || !isTauri()) && (
+          {(installing || progressError || !isTauri()) && (
             <div className="space-y-1">
               <Progress value={progress} />
               <div className="text-xs text-zinc-600 dark:text-zinc-400">
@@ -133,6 +295,142 @@ export default function VersionSwitcher() {
             </div>
           )}
         </div>
+
+        <div className="space-y-2">
+          <div className="flex items-center justify-between">
+            <div className="text-sm text-muted-foreground">历史版本</div>
+            <div className="flex items-center gap-2">
+              <Button
+                variant="outline"
+                size="sm"
+                onClick={() => loadReleases(Math.max(1, (releases?.page ?? 2) - 1))}
+                disabled={releasesLoading || !releases || releases.page <= 1}
+                className="transition-transform active:scale-95 active:translate-y-px"
+              >
+                上一页
+              </Button>
+              <Button
+                variant="outline"
+                size="sm"
+                onClick={() => loadReleases(releases ? releases.page + 1 : 1)}
+                disabled={releasesLoading || (releases !== null && !releases.has_more)}
+                className="transition-transform active:scale-95 active:translate-y-px"
+              >
+                {releases ? '下一页' : '加载发布列表'}
+              </Button>
+            </div>
+          </div>
+          {releases?.releases.map((r) => (
+            <div key={r.tag} className="flex items-center justify-between gap-3 rounded border px-3 py-2">
+              <div className="min-w-0 space-y-0.5">
+                <div className="flex items-center gap-2 text-sm font-medium">
+                  {r.tag}
+                  {r.prerelease && <span className="text-xs text-amber-600">预发布</span>}
+                </div>
+                <div className="truncate text-xs text-zinc-500">
+                  {r.published_at ? new Date(r.published_at).toLocaleString() : '未知日期'}
+                  {' · '}
+                  {r.platform_asset ?? '无匹配当前平台的资产'}
+                </div>
+              </div>
+              <div className="flex shrink-0 items-center gap-2">
+                <Button
+                  variant="outline"
+                  size="sm"
+                  onClick={() => installTag(r.tag, false)}
+                  disabled={!r.platform_asset || installing}
+                  className="transition-transform active:scale-95 active:translate-y-px"
+                >
+                  仅安装
+                </Button>
+                <Button
+                  size="sm"
+                  onClick={() => installTag(r.tag, true)}
+                  disabled={!r.platform_asset || installing}
+                  className="transition-transform active:scale-95 active:translate-y-px"
+                >
+                  安装并设为当前
+                </Button>
+              </div>
+            </div>
+          ))}
+        </div>
+
+        <div className="space-y-2">
+          <div className="flex items-center justify-between">
+            <div className="text-sm text-muted-foreground">已安装版本</div>
+            <div className="flex items-center gap-2">
+              {previousVersion && (
+                <Button
+                  variant="outline"
+                  size="sm"
+                  onClick={rollback}
+                  disabled={busyVersion !== null}
+                  className="transition-transform active:scale-95 active:translate-y-px"
+                >
+                  回滚到 {previousVersion}
+                </Button>
+              )}
+              <Button
+                variant="outline"
+                size="sm"
+                onClick={cleanup}
+                disabled={busyVersion !== null || installing}
+                className="transition-transform active:scale-95 active:translate-y-px"
+              >
+                清理旧版本
+              </Button>
+              <Button
+                variant="outline"
+                size="sm"
+                onClick={loadInstalled}
+                className="transition-transform active:scale-95 active:translate-y-px"
+              >
+                刷新
+              </Button>
+            </div>
+          </div>
+          {rollbackNotice && <div className="text-xs text-amber-600">{rollbackNotice}</div>}
+          {installed.length === 0 && <div className="text-xs text-zinc-500">暂无已安装的版本</div>}
+          {installed.map((v) => (
+            <div key={v.version} className="flex items-center justify-between gap-3 rounded border px-3 py-2">
+              <div className="min-w-0 space-y-0.5">
+                <div className="flex items-center gap-2 text-sm font-medium">
+                  {v.version}
+                  {v.current && <span className="text-xs text-green-600">当前</span>}
+                  {v.running && <span className="text-xs text-blue-600">运行中</span>}
+                </div>
+                <div className="truncate text-xs text-zinc-500" title={v.path}>
+                  {v.version_output ?? '无法读取版本信息'}
+                </div>
+                <div className="text-xs text-zinc-500">
+                  {formatSize(v.size_bytes)}
+                  {v.installed_at && ` · 安装于 ${new Date(v.installed_at).toLocaleString()}`}
+                </div>
+              </div>
+              <div className="flex shrink-0 items-center gap-2">
+                <Button
+                  variant="outline"
+                  size="sm"
+                  onClick={() => switchVersion(v.version)}
+                  disabled={v.current || busyVersion !== null}
+                  className="transition-transform active:scale-95 active:translate-y-px"
+                >
+                  设为当前
+                </Button>
+                <Button
+                  variant="outline"
+                  size="sm"
+                  onClick={() => removeVersion(v.version)}
+                  disabled={v.current || v.running || busyVersion !== null}
+                  className="transition-transform active:scale-95 active:translate-y-px"
+                >
+                  删除
+                </Button>
+              </div>
+            </div>
+          ))}
+        </div>
       </CardContent>
     </Card>
   )
@@ -1,6 +1,6 @@
 'use client'
 
-import { useState } from 'react'
+import { useEffect, useState } from 'react'
 import { useMihomo } from '@/hooks/useMihomo'
 import VersionSwitcher from './components/VersionSwitcher'
 import { Card, CardContent, CardHeader, CardTitle, CardDescription } from '@/components/ui/card'
@@ -15,6 +15,15 @@ export default function CorePage() {
   const [tunHint, setTunHint] = useState<null | TunHint>(null)
   const [svcStatus, setSvcStatus] = useState<string>('unknown')
 
+  useEffect(() => {
+    // 恢复上次启动内核所用的配置
+    invoke<{ last_profile: string | null }>('get_last_session')
+      .then((s) => {
+        if (s.last_profile) setConfigPath(s.last_profile)
+      })
+      .catch(() => {})
+  }, [])
+
   const pickConfig = async () => {
     try {
       const { open } = await import('@tauri-apps/plugin-dialog')
@@ -53,6 +62,8 @@ export default function CorePage() {
               className={
                 status === 'running'
                   ? 'text-green-600'
+                  : status === 'starting'
+                  ? 'text-amber-600'
                   : status === 'error'
                   ? 'text-red-600'
                   : 'text-zinc-600'
@@ -90,7 +101,7 @@ export default function CorePage() {
             </Button>
             <Button
               variant="outline"
-              disabled={isLoading}
+              disabled={isLoading && status !== 'starting'}
               onClick={stop}
               className="transition-transform active:scale-95 active:translate-y-px"
             >
@@ -0,0 +1,101 @@
+'use client'
+
+import { useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { Card, CardContent, CardHeader, CardTitle, CardDescription } from '@/components/ui/card'
+import { Button } from '@/components/ui/button'
+import { Input } from '@/components/ui/input'
+import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from '@/components/ui/select'
+
+type LogLevel = 'debug' | 'info' | 'warning' | 'error'
+type LogEntry = { seq: number; timestamp: number; level: LogLevel; stream: 'stdout' | 'stderr'; message: string }
+type LogPage = { entries: LogEntry[]; total: number }
+
+const MAX_LINES = 1000
+const LEVEL_ORDER: LogLevel[] = ['debug', 'info', 'warning', 'error']
+
+export default function LogsPage() {
+  const [entries, setEntries] = useState<LogEntry[]>([])
+  const [level, setLevel] = useState<LogLevel>('info')
+  const [keyword, setKeyword] = useState('')
+
+  const reload = async () => {
+    try {
+      const page = await invoke<LogPage>('get_core_logs', { query: { level, keyword, limit: MAX_LINES } })
+      setEntries(page.entries)
+    } catch (e) {
+      console.warn('get_core_logs failed', e)
+    }
+  }
+
+  useEffect(() => {
+    reload()
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [level, keyword])
+
+  useEffect(() => {
+    const unlisten = listen<LogEntry & { instance: string }>('core_log', (e) => {
+      const entry = e.payload
+      // 此页面只展示默认实例的日志
+      if (entry.instance !== 'default') return
+      if (LEVEL_ORDER.indexOf(entry.level) < LEVEL_ORDER.indexOf(level)) return
+      if (keyword && !entry.message.toLowerCase().includes(keyword.toLowerCase())) return
+      setEntries((prev) => [...prev, entry].slice(-MAX_LINES))
+    })
+    return () => {
+      unlisten.then((f) => f())
+    }
+  }, [level, keyword])
+
+  return (
+    <div className="mx-auto max-w-4xl p-6 space-y-6">
+      <h1 className="text-2xl font-semibold">内核日志</h1>
+      <Card>
+        <CardHeader>
+          <CardTitle>实时日志</CardTitle>
+          <CardDescription>按级别与关键字过滤内核输出</CardDescription>
+        </CardHeader>
+        <CardContent className="space-y-3">
+          <div className="flex items-center gap-2">
+            <div className="w-32">
+              <Select value={level} onValueChange={(v) => setLevel(v as LogLevel)}>
+                <SelectTrigger>
+                  <SelectValue placeholder="级别" />
+                </SelectTrigger>
+                <SelectContent>
+                  {LEVEL_ORDER.map((l) => (
+                    <SelectItem key={l} value={l}>
+                      {l}
+                    </SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
+            </div>
+            <Input placeholder="关键字" value={keyword} onChange={(e) => setKeyword(e.target.value)} />
+            <Button
+              variant="outline"
+              onClick={async () => {
+                await invoke('clear_core_logs').catch(() => {})
+                setEntries([])
+              }}
+              className="transition-transform active:scale-95 active:translate-y-px"
+            >
+              清空
+            </Button>
+          </div>
+          <div className="h-[60vh] overflow-auto rounded-md border bg-zinc-50 dark:bg-zinc-900 p-2 font-mono text-xs">
+            {entries.map((e) => (
+              <div
+                key={e.seq}
+                className={e.level === 'error' ? 'text-red-600' : e.level === 'warning' ? 'text-amber-600' : ''}
+              >
+                <span className="text-zinc-500">{new Date(e.timestamp).toLocaleTimeString()}</span> [{e.level}] {e.message}
+              </div>
+            ))}
+          </div>
+        </CardContent>
+      </Card>
+    </div>
+  )
+}
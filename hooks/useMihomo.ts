@@ -1,9 +1,21 @@
 import { useEffect, useState } from 'react'
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 
-type CoreStatus = 'running' | 'stopped' | 'error'
+type CoreStatus = 'starting' | 'running' | 'stopped' | 'error'
 
-export function useMihomo() {
+export type CoreStatusEvent = {
+  instance: string
+  status: CoreStatus
+  pid: number | null
+  last_exit: { code: number | null; signal: number | null; at: number } | null
+  restart_count: number
+  next_restart_in_ms: number | null
+  message: string | null
+}
+
+// 未指定时操作后端的默认实例
+export function useMihomo(instance: string = 'default') {
   const [status, setStatus] = useState<CoreStatus>('stopped')
   const [isLoading, setIsLoading] = useState(false)
   const [error, setError] = useState<string | null>(null)
@@ -12,12 +24,19 @@ export function useMihomo() {
     setIsLoading(true)
     setError(null)
     try {
-      await invoke('start_core', { configPath, needPrivilege: false })
+      await invoke('start_core', { instance, configPath, needPrivilege: false })
       setStatus('running')
     } catch (err: unknown) {
-      const msg = err instanceof Error ? err.message : String(err)
+      // start_core 返回结构化错误：{ kind: 'failed' | 'port_conflict', message, conflicts? }
+      const msg =
+        err instanceof Error
+          ? err.message
+          : typeof err === 'object' && err !== null && 'message' in err
+          ? String((err as { message: unknown }).message)
+          : String(err)
       setError(msg || '启动失败')
-      setStatus('error')
+      // 等待就绪期间被停止时保留事件推送的 stopped
+      setStatus((s) => (s === 'stopped' ? s : 'error'))
     } finally {
       setIsLoading(false)
     }
@@ -27,7 +46,7 @@ export function useMihomo() {
     setIsLoading(true)
     setError(null)
     try {
-      await invoke('stop_core')
+      await invoke('stop_core', { instance })
       setStatus('stopped')
     } catch (err: unknown) {
       const msg = err instanceof Error ? err.message : String(err)
@@ -38,16 +57,25 @@ export function useMihomo() {
   }
 
   useEffect(() => {
-    const id = setInterval(async () => {
-      try {
-        const s = await invoke<string>('get_core_status')
-        if (s === 'running' || s === 'stopped' || s === 'error') {
+    // 初始状态读取一次，之后由后端 core_status_changed 事件推送
+    invoke<string>('get_core_status', { instance })
+      .then((s) => {
+        if (s === 'starting' || s === 'running' || s === 'stopped' || s === 'error') {
           setStatus(s)
         }
-      } catch {}
-    }, 1500)
-    return () => clearInterval(id)
-  }, [])
+      })
+      .catch(() => {})
+    const unlisten = listen<CoreStatusEvent>('core_status_changed', (e) => {
+      if (e.payload.instance !== instance) return
+      setStatus(e.payload.status)
+      if (e.payload.status === 'error' && e.payload.message) {
+        setError(e.payload.message)
+      }
+    })
+    return () => {
+      unlisten.then((f) => f())
+    }
+  }, [instance])
 
   return { status, isLoading, error, start, stop }
 }
@@ -1,7 +1,14 @@
 use std::path::PathBuf;
-use tauri::State;
+use tauri::{AppHandle, State};
 
-use crate::config::manager::{ConfigInfo, ConfigManager, ValidationResult};
+use crate::config::manager::{self, ConfigInfo, ConfigManager, ValidationResult};
+use crate::config::runtime::{RuntimeOverrides, RuntimePreview};
+use crate::commands::settings_commands;
+use crate::config::settings::{SessionSettings, SettingsStore};
+use crate::config::workdir;
+use crate::config::validator;
+use crate::core::registry::CoreRegistry;
+use crate::core::version::VersionManager;
 
 type Shared<T> = tauri::async_runtime::Mutex<T>;
 
@@ -16,17 +23,41 @@ pub async fn load_all_configs(config_manager: State<'_, Shared<ConfigManager>>)
 #[tauri::command]
 pub async fn validate_config(
   config_manager: State<'_, Shared<ConfigManager>>,
+  registry: State<'_, Shared<CoreRegistry>>,
   config_path: String,
+  use_core: Option<bool>,
 ) -> Result<ValidationResult, String> {
-  let mgr = config_manager.lock().await;
-  mgr.validate(&PathBuf::from(config_path)).map_err(|e| e.to_string())
+  let path = PathBuf::from(config_path);
+  if use_core.unwrap_or(false) {
+    let core = installed_core(&registry).await?;
+    let home = config_manager.lock().await.prepare_work_dir(&path).map_err(|e| e.to_string())?;
+    // mihomo -t 在阻塞线程中执行，不占用 ConfigManager 锁
+    return tauri::async_runtime::spawn_blocking(move || manager::validate_with_core_in(&path, &core, &home))
+      .await
+      .map_err(|e| e.to_string())?
+      .map_err(|e| e.to_string());
+  }
+  manager::validate_file(&path).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn import_config(
   config_manager: State<'_, Shared<ConfigManager>>,
+  registry: State<'_, Shared<CoreRegistry>>,
   source_path: String,
+  test_with_core: Option<bool>,
 ) -> Result<String, String> {
+  if test_with_core.unwrap_or(false) {
+    let core = installed_core(&registry).await?;
+    let config = PathBuf::from(&source_path);
+    let test = tauri::async_runtime::spawn_blocking(move || validator::test_with_core(&core, &config, &validator::default_test_home()))
+      .await
+      .map_err(|e| e.to_string())?
+      .map_err(|e| e.to_string())?;
+    if !test.passed {
+      return Err(format!("配置未通过内核测试：\n{}", test.summary()));
+    }
+  }
   let mgr = config_manager.lock().await;
   mgr
     .import_config(&PathBuf::from(source_path))
@@ -46,3 +77,124 @@ pub async fn export_config(
     .map_err(|e| e.to_string())
 }
 
+async fn installed_core(registry: &State<'_, Shared<CoreRegistry>>) -> Result<PathBuf, String> {
+  let reg = registry.lock().await;
+  reg.default_core_path().ok_or_else(|| "尚未安装内核，无法进行内核测试".to_string())
+}
+
+#[tauri::command]
+pub async fn clean_profile_workdir(
+  config_manager: State<'_, Shared<ConfigManager>>,
+  registry: State<'_, Shared<CoreRegistry>>,
+  config_path: String,
+  full: Option<bool>,
+) -> Result<Vec<String>, String> {
+  let mgr = config_manager.lock().await;
+  let dir = mgr.work_dir_for(&PathBuf::from(&config_path));
+  if let Some(id) = registry.lock().await.running_in_work_dir(&dir).await {
+    return Err(format!("该 profile 的内核正在实例 {} 中运行，请先停止内核", id));
+  }
+  log::info!("clean_profile_workdir {} full={:?}", config_path, full);
+  mgr
+    .clean_work_dir(&PathBuf::from(config_path), full.unwrap_or(false))
+    .map(|paths| paths.into_iter().map(|p| p.to_string_lossy().to_string()).collect())
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_profile_workdir(
+  config_manager: State<'_, Shared<ConfigManager>>,
+  config_path: String,
+) -> Result<String, String> {
+  let mgr = config_manager.lock().await;
+  Ok(mgr.work_dir_for(&PathBuf::from(config_path)).to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn get_runtime_overrides(config_manager: State<'_, Shared<ConfigManager>>) -> Result<RuntimeOverrides, String> {
+  let mgr = config_manager.lock().await;
+  Ok(mgr.overrides.clone())
+}
+
+#[tauri::command]
+pub async fn set_runtime_overrides(app: AppHandle, overrides: RuntimeOverrides) -> Result<(), String> {
+  settings_commands::commit(&app, |s| s.profiles.overrides = overrides).await?;
+  Ok(())
+}
+
+#[tauri::command]
+pub async fn preview_runtime_config(
+  app: AppHandle,
+  config_manager: State<'_, Shared<ConfigManager>>,
+  config_path: String,
+) -> Result<RuntimePreview, String> {
+  // 预览显示的 secret 即之后启动使用的值
+  settings_commands::ensure_secret(&app).await?;
+  let mgr = config_manager.lock().await;
+  mgr
+    .preview_runtime_config(&PathBuf::from(config_path))
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_auto_reload(app: AppHandle, enabled: bool) -> Result<(), String> {
+  settings_commands::commit(&app, |s| s.profiles.auto_reload = enabled).await?;
+  Ok(())
+}
+
+/// profile 指定的内核版本；None 表示使用默认内核（cores/current）
+#[tauri::command]
+pub async fn get_profile_core_version(
+  config_manager: State<'_, Shared<ConfigManager>>,
+  config_path: String,
+) -> Result<Option<String>, String> {
+  let mgr = config_manager.lock().await;
+  Ok(mgr.core_version_for(&PathBuf::from(config_path)))
+}
+
+/// 为 profile 指定已安装的内核版本，不改变默认内核；version 为空时恢复默认，下次启动或重载时生效
+#[tauri::command]
+pub async fn set_profile_core_version(
+  app: AppHandle,
+  version_manager: State<'_, Shared<VersionManager>>,
+  config_path: String,
+  version: Option<String>,
+) -> Result<(), String> {
+  let version = version.filter(|v| !v.trim().is_empty());
+  if let Some(v) = version.as_ref() {
+    let vm = version_manager.lock().await;
+    if vm.installed_binary(v).is_none() {
+      return Err(format!("内核版本 {} 未安装", v));
+    }
+  }
+  log::info!("set_profile_core_version {} => {:?}", config_path, version);
+  let name = workdir::profile_name(&PathBuf::from(config_path));
+  settings_commands::commit(&app, |s| {
+    match version {
+      Some(v) => s.profiles.core_versions.insert(name, v),
+      None => s.profiles.core_versions.remove(&name),
+    };
+  })
+  .await?;
+  Ok(())
+}
+
+/// 上次会话保存的状态，前端据此恢复选中的 profile
+#[tauri::command]
+pub async fn get_last_session(store: State<'_, Shared<SettingsStore>>) -> Result<SessionSettings, String> {
+  let store = store.lock().await;
+  Ok(store.settings.session.clone())
+}
+
+/// 设置应用启动时是否自动以上次的 profile 运行内核，以及运行前的等待时长
+#[tauri::command]
+pub async fn set_auto_start(app: AppHandle, enabled: bool, delay_ms: Option<u64>) -> Result<(), String> {
+  settings_commands::commit(&app, |s| {
+    s.session.auto_start = enabled;
+    if let Some(ms) = delay_ms {
+      s.session.auto_start_delay_ms = ms;
+    }
+  })
+  .await?;
+  Ok(())
+}
@@ -1,41 +1,190 @@
+use std::collections::HashSet;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
-use futures_util::StreamExt;
 use serde::Serialize;
-use tauri::{State, Window, Emitter};
+use tauri::{AppHandle, Emitter, Manager, State};
 
-use crate::core::manager::{CoreManager, CoreStatus};
-use crate::core::version::{ReleaseChannel, VersionManager, build_gh_client, fetch_text, verify_sha256};
+use crate::commands::settings_commands;
+use crate::config::manager::ConfigManager;
+use crate::config::settings::SettingsStore;
+use crate::config::validator;
+use crate::core::download::{self, Downloads, INSTALL_PROGRESS_EVENT};
+use crate::core::logfile::{LogFileConfig, LogFileInfo};
+use crate::core::logs::{LogPage, LogQuery};
+use crate::core::manager::{self, ReloadReport, RestartConfig, StopReport};
+use crate::core::mirror::MirrorStatus;
+use crate::core::net;
+use crate::core::ports::PortConflict;
+use crate::core::registry::{CoreRegistry, InstanceInfo, SharedCore, DEFAULT_INSTANCE};
+use crate::core::resources::{ResourceUsage, SAMPLE_INTERVAL};
+use crate::core::version::{CoreRolledBackEvent, GcReport, InstalledVersion, ReleaseChannel, ReleasePage, VersionManager, CORE_ROLLED_BACK_EVENT, DEFAULT_RELEASES_PER_PAGE, fetch_text, probe_installed, verify_binary, verify_sha256_hex};
 
 type Shared<T> = tauri::async_runtime::Mutex<T>;
 
+/// start_core 的结构化错误，前端可据 kind 区分端口冲突与其他失败
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StartCoreError {
+  Failed { message: String },
+  PortConflict { message: String, conflicts: Vec<PortConflict> },
+}
+
+impl From<String> for StartCoreError {
+  fn from(message: String) -> Self {
+    StartCoreError::Failed { message }
+  }
+}
+
+/// 按 id 取内核实例，未指定时为默认实例
+pub(crate) async fn instance_core(
+  registry: &State<'_, Shared<CoreRegistry>>,
+  instance: Option<&str>,
+) -> Result<SharedCore, String> {
+  registry.lock().await.get(instance)
+}
+
 #[tauri::command]
 pub async fn start_core(
-  core_manager: State<'_, Shared<CoreManager>>,
+  app: AppHandle,
+  instance: Option<String>,
   config_path: String,
   _need_privilege: Option<bool>,
-) -> Result<(), String> {
-  let mut mgr = core_manager.lock().await;
-  let path = PathBuf::from(config_path);
-  log::info!("start_core with config: {}", path.display());
-  mgr.start(path)
+  preflight: Option<bool>,
+) -> Result<(), StartCoreError> {
+  start_profile(&app, instance.as_deref(), PathBuf::from(config_path), preflight.unwrap_or(false)).await
+}
+
+/// 以 profile 启动指定实例；start_core 与应用启动时的自动运行共用
+pub(crate) async fn start_profile(
+  app: &AppHandle,
+  instance: Option<&str>,
+  source: PathBuf,
+  preflight: bool,
+) -> Result<(), StartCoreError> {
+  let registry = app.state::<Shared<CoreRegistry>>();
+  let config_manager = app.state::<Shared<ConfigManager>>();
+  let id = instance.unwrap_or(DEFAULT_INSTANCE).trim().to_string();
+  if id.is_empty() {
+    return Err("实例 id 不能为空".to_string().into());
+  }
+  let core = {
+    let mut reg = registry.lock().await;
+    // 同一 profile 共用工作目录与运行时配置，不允许被两个实例同时运行
+    if let Some(other) = reg.running_with_source(&source, &id).await {
+      return Err(format!("该配置已在实例 {} 中运行", other).into());
+    }
+    reg.get_or_create(Some(&id))?
+  };
+  // 准备 profile 工作目录，并生成合并了运行时覆盖项的配置；原始文件保持不变
+  settings_commands::ensure_secret(app).await?;
+  let spec = config_manager.lock().await.prepare_launch(&source)?;
+  let mut mgr = core.lock().await;
+  // 清理该实例上次会话遗留的内核进程（避免端口被占用）
+  if let Some(pid) = mgr.reap_stale_core().await {
+    log::info!("terminated stale core process pid={} instance={}", pid, id);
+  }
+  log::info!("start_core [{}] with config: {} (runtime {})", id, spec.source.display(), spec.config.display());
+  // 可选：启动前先用 mihomo -t 检查配置，未通过则拒绝启动；测试期间不持有实例锁
+  if preflight {
+    let core_path = mgr.resolve_core(spec.core_version.as_deref())?;
+    drop(mgr);
+    let config = spec.config.clone();
+    let home = spec.work_dir.clone().unwrap_or_else(validator::default_test_home);
+    let test = tauri::async_runtime::spawn_blocking(move || validator::test_with_core(&core_path, &config, &home))
+      .await
+      .map_err(|e| e.to_string())?
+      .map_err(|e| e.to_string())?;
+    if !test.passed {
+      return Err(format!("配置未通过内核测试：\n{}", test.summary()).into());
+    }
+    mgr = core.lock().await;
+    if mgr.child.is_some() {
+      return Err(format!("内核测试期间实例 {} 已被启动", id).into());
+    }
+  }
+  let conflicts = mgr.check_ports(&spec.config)?;
+  if !conflicts.is_empty() {
+    let message = conflicts.iter().map(|c| c.message.clone()).collect::<Vec<_>>().join("\n");
+    log::warn!("start_core aborted, port conflicts: {}", message);
+    return Err(StartCoreError::PortConflict { message, conflicts });
+  }
+  let probe = mgr.start(spec)?;
+  drop(mgr);
+  // 等待就绪期间不持有实例锁，用户可随时停止
+  manager::wait_ready(&core, probe).await?;
+  let version = core.lock().await.running_version();
+  // 默认实例的 profile 作为下次启动恢复的对象
+  if id == DEFAULT_INSTANCE {
+    settings_commands::commit(app, |s| {
+      s.session.last_profile = Some(source.clone());
+      s.session.last_version = version;
+      s.session.was_running = true;
+    })
+    .await?;
+  }
+  config_manager.lock().await.current_config = Some(source);
+  Ok(())
 }
 
+/// 列出所有内核实例及其状态
 #[tauri::command]
-pub async fn stop_core(core_manager: State<'_, Shared<CoreManager>>) -> Result<(), String> {
-  let mut mgr = core_manager.lock().await;
-  log::info!("stop_core called");
-  mgr.stop()
+pub async fn list_core_instances(registry: State<'_, Shared<CoreRegistry>>) -> Result<Vec<InstanceInfo>, String> {
+  let reg = registry.lock().await;
+  Ok(reg.list().await)
 }
 
+/// 删除已停止的内核实例
 #[tauri::command]
-pub async fn get_core_status(core_manager: State<'_, Shared<CoreManager>>) -> Result<String, String> {
-  let mut mgr = core_manager.lock().await;
-  let status = match mgr.get_status() {
-    CoreStatus::Running => "running",
-    CoreStatus::Stopped => "stopped",
-    CoreStatus::Error => "error",
-  };
+pub async fn remove_core_instance(registry: State<'_, Shared<CoreRegistry>>, instance: String) -> Result<(), String> {
+  let mut reg = registry.lock().await;
+  log::info!("remove_core_instance {}", instance);
+  reg.remove(&instance).await
+}
+
+#[tauri::command]
+pub async fn check_port_conflicts(
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+  config_path: String,
+) -> Result<Vec<PortConflict>, String> {
+  let core = instance_core(&registry, instance.as_deref()).await?;
+  let mgr = core.lock().await;
+  mgr.check_ports(&PathBuf::from(config_path))
+}
+
+#[tauri::command]
+pub async fn stop_core(
+  app: AppHandle,
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+  grace_ms: Option<u64>,
+) -> Result<StopReport, String> {
+  let core = instance_core(&registry, instance.as_deref()).await?;
+  let mut mgr = core.lock().await;
+  log::info!("stop_core called, grace_ms={:?}", grace_ms);
+  let report = match grace_ms {
+    Some(ms) => mgr.stop_with_grace(std::time::Duration::from_millis(ms)).await,
+    None => mgr.stop().await,
+  }?;
+  log::info!("core stopped via {:?} in {} ms", report.method, report.elapsed_ms);
+  // 用户手动停止后，下次启动应用不再自动运行
+  if mgr.instance == DEFAULT_INSTANCE {
+    drop(mgr);
+    settings_commands::commit(&app, |s| s.session.was_running = false).await?;
+  }
+  Ok(report)
+}
+
+#[tauri::command]
+pub async fn get_core_status(
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+) -> Result<String, String> {
+  let core = instance_core(&registry, instance.as_deref()).await?;
+  let mut mgr = core.lock().await;
+  let status = mgr.get_status().as_str();
   log::debug!("get_core_status => {}", status);
   Ok(status.to_string())
 }
@@ -43,107 +192,637 @@ pub async fn get_core_status(core_manager: State<'_, Shared<CoreManager>>) -> Re
 // 已移除手动设置/读取 core_path 的命令，统一采用固定路径。
 
 #[tauri::command]
-pub async fn restart_core(core_manager: State<'_, Shared<CoreManager>>) -> Result<(), String> {
-  let mut mgr = core_manager.lock().await;
-  mgr.restart()
+pub async fn restart_core(
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+) -> Result<(), String> {
+  let core = instance_core(&registry, instance.as_deref()).await?;
+  manager::restart(&core).await
+}
+
+/// 重新生成运行时配置并应用到运行中的内核；未指定 config_path 时重载当前 profile
+#[tauri::command]
+pub async fn reload_core_config(
+  app: AppHandle,
+  registry: State<'_, Shared<CoreRegistry>>,
+  config_manager: State<'_, Shared<ConfigManager>>,
+  instance: Option<String>,
+  config_path: Option<String>,
+) -> Result<ReloadReport, String> {
+  let core = instance_core(&registry, instance.as_deref()).await?;
+  let source = match config_path {
+    Some(p) => PathBuf::from(p),
+    None => core.lock().await.source_config.clone().ok_or_else(|| "尚未指定配置文件".to_string())?,
+  };
+  settings_commands::ensure_secret(&app).await?;
+  let spec = config_manager.lock().await.prepare_launch(&source)?;
+  let report = manager::reload(&core, spec).await?;
+  log::info!("reload_core_config via {:?} ({:?})", report.method, report.reason);
+  Ok(report)
+}
+
+#[tauri::command]
+pub async fn get_restart_policy(
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+) -> Result<RestartConfig, String> {
+  let core = instance_core(&registry, instance.as_deref()).await?;
+  let mgr = core.lock().await;
+  Ok(mgr.restart_config.clone())
+}
+
+/// 未指定实例时写入设置并应用到所有实例；指定实例时仅对该实例生效且不保存
+#[tauri::command]
+pub async fn set_restart_policy(
+  app: AppHandle,
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+  config: RestartConfig,
+) -> Result<(), String> {
+  log::info!("set_restart_policy {:?} instance={:?}", config, instance);
+  match instance {
+    None => settings_commands::commit(&app, |s| s.core.restart = config).await.map(|_| ()),
+    Some(id) => {
+      let core = instance_core(&registry, Some(&id)).await?;
+      core.lock().await.set_restart_config(config);
+      Ok(())
+    }
+  }
+}
+
+#[tauri::command]
+pub async fn get_core_logs(
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+  query: Option<LogQuery>,
+) -> Result<LogPage, String> {
+  let logs = instance_core(&registry, instance.as_deref()).await?.lock().await.logs.clone();
+  Ok(logs.query(&query.unwrap_or_default()))
+}
+
+/// 获取内核进程最近的资源占用采样
+#[tauri::command]
+pub async fn get_core_resource_usage(
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+) -> Result<ResourceUsage, String> {
+  let history = instance_core(&registry, instance.as_deref()).await?.lock().await.resources.clone();
+  Ok(ResourceUsage { interval_ms: SAMPLE_INTERVAL.as_millis() as u64, samples: history.snapshot() })
+}
+
+#[tauri::command]
+pub async fn clear_core_logs(
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+) -> Result<(), String> {
+  let logs = instance_core(&registry, instance.as_deref()).await?.lock().await.logs.clone();
+  logs.clear();
+  Ok(())
+}
+
+#[tauri::command]
+pub async fn list_core_log_files(
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+) -> Result<Vec<LogFileInfo>, String> {
+  let files = instance_core(&registry, instance.as_deref()).await?.lock().await.log_files.clone();
+  files.list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn export_core_logs(
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+  target_path: String,
+  from_ms: Option<u64>,
+  to_ms: Option<u64>,
+) -> Result<usize, String> {
+  let files = instance_core(&registry, instance.as_deref()).await?.lock().await.log_files.clone();
+  log::info!("export_core_logs to {} range={:?}..{:?}", target_path, from_ms, to_ms);
+  files
+    .export(&PathBuf::from(target_path), from_ms, to_ms)
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn purge_core_logs(
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+  older_than_days: Option<u64>,
+) -> Result<usize, String> {
+  let files = instance_core(&registry, instance.as_deref()).await?.lock().await.log_files.clone();
+  files
+    .purge(older_than_days.map(|d| std::time::Duration::from_secs(d * 24 * 3600)))
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_log_file_config(
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+) -> Result<LogFileConfig, String> {
+  let files = instance_core(&registry, instance.as_deref()).await?.lock().await.log_files.clone();
+  Ok(files.config())
+}
+
+/// 未指定实例时写入设置并应用到所有实例；指定实例时仅对该实例生效且不保存
+#[tauri::command]
+pub async fn set_log_file_config(
+  app: AppHandle,
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+  config: LogFileConfig,
+) -> Result<(), String> {
+  match instance {
+    None => settings_commands::commit(&app, |s| s.log_file = config).await.map(|_| ()),
+    Some(id) => {
+      let files = instance_core(&registry, Some(&id)).await?.lock().await.log_files.clone();
+      files.set_config(config);
+      Ok(())
+    }
+  }
 }
 
 #[tauri::command]
 pub async fn fetch_latest_version(
+  app: AppHandle,
   version_manager: State<'_, Shared<VersionManager>>,
-  channel: String,
+  channel: Option<String>,
 ) -> Result<String, String> {
-  let ch = match channel.as_str() {
-    "stable" => ReleaseChannel::Stable,
-    "dev" => ReleaseChannel::Dev,
-    _ => return Err("无效的渠道".into()),
-  };
+  let client = net::http_client(&app).await?;
   let mgr = version_manager.lock().await;
-  log::info!("fetch_latest_version channel={}", channel);
+  let ch = parse_channel(channel.as_deref(), mgr.channel)?;
+  log::info!("fetch_latest_version channel={:?}", ch);
   mgr
-    .fetch_latest(ch)
+    .fetch_latest(&client, ch)
     .await
     .map(|info| info.version)
     .map_err(|e| e.to_string())
 }
 
+/// 开始下载并安装最新版本，立即返回下载 id；进度与结果通过 version_install_progress 推送
 #[tauri::command]
 pub async fn download_install_latest(
-  window: Window,
-  core_manager: State<'_, Shared<CoreManager>>,
+  app: AppHandle,
+  downloads: State<'_, Shared<Downloads>>,
   version_manager: State<'_, Shared<VersionManager>>,
-  channel: String,
+  channel: Option<String>,
 ) -> Result<String, String> {
-  let ch = match channel.as_str() {
-    "stable" => ReleaseChannel::Stable,
-    "dev" => ReleaseChannel::Dev,
-    _ => return Err("无效的渠道".into()),
+  let ch = parse_channel(channel.as_deref(), version_manager.lock().await.channel)?;
+  start_install(app, &downloads, InstallSource::Channel(ch), true).await
+}
+
+/// 按页列出内核的发布，附带是否有匹配当前平台的资产
+#[tauri::command]
+pub async fn list_core_releases(
+  app: AppHandle,
+  version_manager: State<'_, Shared<VersionManager>>,
+  page: Option<u32>,
+  per_page: Option<u32>,
+) -> Result<ReleasePage, String> {
+  let client = net::http_client(&app).await?;
+  let vm = version_manager.lock().await;
+  vm
+    .list_releases(&client, page.unwrap_or(1), per_page.unwrap_or(DEFAULT_RELEASES_PER_PAGE))
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// 已配置的镜像及其请求成功、失败统计
+#[tauri::command]
+pub async fn get_mirror_health(version_manager: State<'_, Shared<VersionManager>>) -> Result<Vec<MirrorStatus>, String> {
+  Ok(version_manager.lock().await.mirror_status())
+}
+
+/// 开始下载并安装指定 tag 的发布，立即返回下载 id；make_current 默认为 true，为 false 时仅安装，可供 profile 指定使用
+#[tauri::command]
+pub async fn download_install_version(
+  app: AppHandle,
+  downloads: State<'_, Shared<Downloads>>,
+  tag: String,
+  make_current: Option<bool>,
+) -> Result<String, String> {
+  start_install(app, &downloads, InstallSource::Tag(tag), make_current.unwrap_or(true)).await
+}
+
+/// 取消进行中的下载；已进入安装阶段时不再中断
+#[tauri::command]
+pub async fn cancel_download(downloads: State<'_, Shared<Downloads>>, id: String) -> Result<(), String> {
+  log::info!("cancel_download {}", id);
+  downloads.lock().await.cancel(&id)
+}
+
+/// 进行中的下载 id
+#[tauri::command]
+pub async fn get_active_download(downloads: State<'_, Shared<Downloads>>) -> Result<Option<String>, String> {
+  Ok(downloads.lock().await.active())
+}
+
+enum InstallSource {
+  Channel(ReleaseChannel),
+  Tag(String),
+}
+
+enum InstallError {
+  Cancelled,
+  Failed(String),
+}
+
+impl From<String> for InstallError {
+  fn from(message: String) -> Self {
+    InstallError::Failed(message)
+  }
+}
+
+/// version_install_progress 事件负载
+#[derive(Serialize, Clone)]
+struct ProgressPayload<'a> {
+  id: &'a str,
+  stage: &'a str,
+  progress: u8,
+  message: Option<String>,
+}
+
+fn emit_progress(app: &AppHandle, id: &str, stage: &str, progress: u8, message: Option<String>) {
+  let _ = app.emit(INSTALL_PROGRESS_EVENT, ProgressPayload { id, stage, progress, message });
+}
+
+fn download_progress(app: &AppHandle, id: &str, received: u64, total: Option<u64>) {
+  let pct = match total {
+    Some(t) if t > 0 => ((received as f64 / t as f64) * 90.0).clamp(1.0, 90.0) as u8, // 下载占前90%
+    // 未知大小，伪进度
+    _ => ((received / (1024 * 1024)) % 90) as u8, // 每MB+1直到90
   };
+  emit_progress(app, id, "下载中", pct, None);
+}
+
+fn url_host(url: &str) -> Option<String> {
+  reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string))
+}
+
+/// 登记下载并在后台执行安装；同一时间只允许一个安装任务
+async fn start_install(
+  app: AppHandle,
+  downloads: &State<'_, Shared<Downloads>>,
+  source: InstallSource,
+  make_current: bool,
+) -> Result<String, String> {
+  let (id, cancel) = downloads.lock().await.begin()?;
+  let task_id = id.clone();
+  tauri::async_runtime::spawn(async move {
+    let result = run_install(&app, &task_id, source, make_current, &cancel).await;
+    app.state::<Shared<Downloads>>().lock().await.finish(&task_id);
+    match result {
+      Ok(path) => emit_progress(&app, &task_id, "完成", 100, Some(path)),
+      Err(InstallError::Cancelled) => {
+        log::info!("download {} cancelled", task_id);
+        emit_progress(&app, &task_id, "cancelled", 0, None);
+      }
+      Err(InstallError::Failed(e)) => {
+        log::error!("download {} failed: {}", task_id, e);
+        emit_progress(&app, &task_id, "错误", 100, Some(e));
+      }
+    }
+  });
+  Ok(id)
+}
+
+/// 规划、下载、校验并安装，返回安装后的内核路径
+async fn run_install(
+  app: &AppHandle,
+  id: &str,
+  source: InstallSource,
+  make_current: bool,
+  cancel: &AtomicBool,
+) -> Result<String, InstallError> {
+  let registry = app.state::<Shared<CoreRegistry>>();
+  let version_manager = app.state::<Shared<VersionManager>>();
+  let store = app.state::<Shared<SettingsStore>>();
 
-  // 进度事件负载
-  #[derive(Serialize, Clone)]
-  struct ProgressPayload<'a> { stage: &'a str, progress: u8, message: Option<String> }
+  // 发布信息、资产与校验文件共用按代理设置构建的客户端
+  let client = net::http_client(app).await?;
 
   // 规划下载（资产与校验）
   let plan = {
     let vm = version_manager.lock().await;
-    vm.plan_download(ch).await.map_err(|e| e.to_string())?
+    match &source {
+      InstallSource::Channel(ch) => vm.plan_download(&client, *ch).await,
+      InstallSource::Tag(tag) => vm.plan_download_tag(&client, tag).await,
+    }
+    .map_err(|e| e.to_string())?
+  };
+  log::info!("download plan: version={}, asset={} url={} checksum={:?} mirror={}", plan.version, plan.asset_name, plan.asset_url, plan.checksum_url, plan.mirror.label());
+  if cancel.load(Ordering::SeqCst) {
+    return Err(InstallError::Cancelled);
+  }
+
+  emit_progress(app, id, "开始下载", 0, None);
+
+  // 流式下载到 cores 目录下的 .part 文件，中断后以 Range 续传；取消时删除 part 文件。
+  // 规划所用的镜像失败后依次换用其他镜像，主机不同时丢弃 part 文件从头下载
+  let (routes, part) = {
+    let vm = version_manager.lock().await;
+    (vm.download_routes(&plan.mirror, &plan.asset_url), vm.part_path(&plan.asset_name))
   };
-  log::info!("download plan: version={}, asset={} url={} checksum={:?}", plan.version, plan.asset_name, plan.asset_url, plan.checksum_url);
-
-  let _ = window.emit("version_install_progress", ProgressPayload { stage: "开始下载", progress: 0, message: None });
-
-  // 构建客户端并下载（流式）
-  let client = build_gh_client().map_err(|e| e.to_string())?;
-  let resp = client.get(&plan.asset_url).send().await.map_err(|e| e.to_string())?;
-  let total = resp.content_length();
-  let mut stream = resp.bytes_stream();
-  let mut received: u64 = 0;
-  let mut buf: Vec<u8> = Vec::with_capacity(total.unwrap_or(0) as usize);
-  while let Some(chunk) = stream.next().await {
-    let chunk = chunk.map_err(|e| e.to_string())?;
-    received += chunk.len() as u64;
-    buf.extend_from_slice(&chunk);
-    if let Some(t) = total {
-      let pct = ((received as f64 / t as f64) * 90.0).min(90.0).max(1.0) as u8; // 下载占前90%
-      if pct % 5 == 0 { log::debug!("downloading... {}%", pct); }
-      let _ = window.emit("version_install_progress", ProgressPayload { stage: "下载中", progress: pct, message: None });
-    } else {
-      // 未知大小，伪进度
-      let pct = ((received / (1024 * 1024)) % 90) as u8; // 每MB+1直到90
-      let _ = window.emit("version_install_progress", ProgressPayload { stage: "下载中", progress: pct, message: None });
-    }
-  }
-
-  // 校验
-  if let Some(url) = plan.checksum_url.as_ref() {
-    let _ = window.emit("version_install_progress", ProgressPayload { stage: "校验中", progress: 92, message: None });
-    log::info!("verifying checksum from {}", url);
-    let text = fetch_text(&client, url, "checksums").await.map_err(|e| e.to_string())?;
-    verify_sha256(&buf, &text).map_err(|e| e.to_string())?;
+  let mut errors = Vec::new();
+  let mut last_host = None;
+  let mut downloaded = None;
+  for (mirror, url) in routes {
+    let host = url_host(&url);
+    if last_host.as_ref().is_some_and(|h| *h != host) {
+      let _ = std::fs::remove_file(&part);
+    }
+    last_host = Some(host);
+    log::info!("downloading {} via {}", url, mirror.label());
+    let started = Instant::now();
+    match download::download_to_file(&client, &url, &part, cancel, |received, total| download_progress(app, id, received, total)).await {
+      Ok(d) => {
+        version_manager.lock().await.mirror_health.record_success(&mirror, started.elapsed());
+        downloaded = Some(d);
+        break;
+      }
+      Err(e) if download::is_cancelled(&e) => {
+        let _ = std::fs::remove_file(&part);
+        return Err(InstallError::Cancelled);
+      }
+      Err(e) => {
+        log::warn!("download via {} failed: {:#}", mirror.label(), e);
+        version_manager.lock().await.mirror_health.record_failure(&mirror, &format!("{:#}", e));
+        errors.push(format!("{}: {:#}", mirror.label(), e));
+      }
+    }
+  }
+  let downloaded = downloaded.ok_or_else(|| format!("所有镜像均下载失败:\n{}", errors.join("\n")))?;
+  log::info!("downloaded {} bytes to {} sha256={}", downloaded.size, downloaded.path.display(), downloaded.sha256);
+
+  // 校验；不匹配时删除 part 文件，避免下次续传损坏的内容
+  if let Some(origin) = plan.checksum_url.as_ref() {
+    emit_progress(app, id, "校验中", 92, None);
+    let routes = version_manager.lock().await.download_routes(&plan.mirror, origin);
+    let mut errors = Vec::new();
+    let mut text = None;
+    for (mirror, url) in routes {
+      if cancel.load(Ordering::SeqCst) {
+        let _ = std::fs::remove_file(&part);
+        return Err(InstallError::Cancelled);
+      }
+      log::info!("verifying checksum from {}", url);
+      let started = Instant::now();
+      match fetch_text(&client, &url, "checksums").await {
+        Ok(t) => {
+          version_manager.lock().await.mirror_health.record_success(&mirror, started.elapsed());
+          text = Some(t);
+          break;
+        }
+        Err(e) => {
+          log::warn!("checksums via {} failed: {:#}", mirror.label(), e);
+          version_manager.lock().await.mirror_health.record_failure(&mirror, &format!("{:#}", e));
+          errors.push(format!("{}: {:#}", mirror.label(), e));
+        }
+      }
+    }
+    let text = text.ok_or_else(|| format!("所有镜像均无法获取校验文件:\n{}", errors.join("\n")))?;
+    if let Err(e) = verify_sha256_hex(&downloaded.sha256, &text) {
+      let _ = std::fs::remove_file(&part);
+      return Err(e.to_string().into());
+    }
+  }
+  if cancel.load(Ordering::SeqCst) {
+    let _ = std::fs::remove_file(&part);
+    return Err(InstallError::Cancelled);
   }
 
   // 安装
   let installed = {
     let mut vm = version_manager.lock().await;
-    let _ = window.emit("version_install_progress", ProgressPayload { stage: "安装中", progress: 95, message: None });
+    emit_progress(app, id, "安装中", 95, None);
     log::info!("installing version {}", plan.version);
-    vm.install_from_bytes(&plan.version, &plan.asset_name, &buf).map_err(|e| e.to_string())?
+    let result = vm.install_from_file(&plan.version, &plan.asset_name, &part, make_current);
+    let _ = std::fs::remove_file(&part);
+    result.map_err(|e| e.to_string())?
   };
 
-  // 自动更新 core_path
-  let mut cm = core_manager.lock().await;
-  cm.set_core_path(installed.clone());
-  log::info!("installed at {} and set as current core", installed.display());
+  if make_current {
+    // 自动更新默认内核，仍使用旧默认内核的实例一并切换
+    registry.lock().await.set_default_core_path(installed.clone()).await;
+    log::info!("installed at {} and set as current core", installed.display());
+    if version_manager.lock().await.auto_rollback {
+      emit_progress(app, id, "验证中", 97, None);
+      verify_upgrade(app, &registry, &version_manager, &plan.version).await?;
+    }
+  } else {
+    log::info!("installed at {}", installed.display());
+  }
 
-  let _ = window.emit(
-    "version_install_progress",
-    ProgressPayload { stage: "完成", progress: 100, message: None },
-  );
+  let (keep, auto_gc) = {
+    let store = store.lock().await;
+    (store.settings.core.keep_versions, store.settings.core.auto_gc)
+  };
+  if auto_gc {
+    let report = collect_garbage(&registry, &version_manager, &store, keep, Some(&plan.version), false).await;
+    if !report.removed.is_empty() {
+      log::info!("removed {} old core versions, freed {} bytes", report.removed.len(), report.freed_bytes);
+    }
+  }
 
   Ok(installed.to_string_lossy().to_string())
 }
 
+/// 按保留策略清理旧版本；dry_run 为 true 时只报告将会删除的版本与释放的空间
+#[tauri::command]
+pub async fn gc_core_versions(
+  registry: State<'_, Shared<CoreRegistry>>,
+  version_manager: State<'_, Shared<VersionManager>>,
+  store: State<'_, Shared<SettingsStore>>,
+  dry_run: Option<bool>,
+  keep: Option<usize>,
+) -> Result<GcReport, String> {
+  let keep = match keep {
+    Some(n) => n,
+    None => store.lock().await.settings.core.keep_versions,
+  };
+  let dry_run = dry_run.unwrap_or(false);
+  log::info!("gc_core_versions keep={} dry_run={}", keep, dry_run);
+  Ok(collect_garbage(&registry, &version_manager, &store, keep, None, dry_run).await)
+}
+
+/// profile 指定的版本、运行中的版本与 just_installed 不会被清理
+async fn collect_garbage(
+  registry: &State<'_, Shared<CoreRegistry>>,
+  version_manager: &State<'_, Shared<VersionManager>>,
+  store: &State<'_, Shared<SettingsStore>>,
+  keep: usize,
+  just_installed: Option<&str>,
+  dry_run: bool,
+) -> GcReport {
+  let mut protected: HashSet<String> = store.lock().await.settings.profiles.core_versions.values().cloned().collect();
+  protected.extend(running_versions(registry).await);
+  protected.extend(just_installed.map(str::to_string));
+  version_manager.lock().await.collect_garbage(keep, &protected, dry_run)
+}
+
+/// 验证刚设为 current 的版本：先执行 `mihomo -v`，再重启使用默认内核的运行中实例并等待就绪；
+/// 任一步失败则回滚到之前的版本、以旧内核重新启动这些实例并推送 core_rolled_back
+async fn verify_upgrade(
+  emitter: &impl Emitter,
+  registry: &State<'_, Shared<CoreRegistry>>,
+  version_manager: &State<'_, Shared<VersionManager>>,
+  version: &str,
+) -> Result<(), String> {
+  // 只在锁内解析路径，执行 `mihomo -v` 时释放锁，避免阻塞其他版本操作
+  let bin = version_manager.lock().await.installed_binary(version);
+  let probe = match bin {
+    None => Err(format!("内核版本 {} 未安装", version)),
+    Some(bin) => tauri::async_runtime::spawn_blocking(move || verify_binary(&bin).map_err(|e| e.to_string()))
+      .await
+      .unwrap_or_else(|e| Err(e.to_string())),
+  };
+  let failure = match probe {
+    Err(e) => Some(e),
+    Ok(output) => {
+      log::info!("new core reports: {}", output);
+      let (_, err) = restart_default_core_instances(registry).await;
+      err
+    }
+  };
+  let Some(reason) = failure else { return Ok(()) };
+
+  log::warn!("core {} failed verification: {}", version, reason);
+  let rolled = version_manager.lock().await.rollback();
+  let (from, to, path) = match rolled {
+    Ok(r) => r,
+    Err(e) => return Err(format!("新内核 {} 验证失败（{}），且无法回滚: {}", version, reason, e)),
+  };
+  registry.lock().await.set_default_core_path(path).await;
+  let (restarted, err) = restart_default_core_instances(registry).await;
+  if let Some(e) = err {
+    log::error!("restart with rolled back core {} failed: {}", to, e);
+  }
+  log::info!("rolled back core {} -> {}", from, to);
+  let _ = emitter.emit(CORE_ROLLED_BACK_EVENT, CoreRolledBackEvent { from, to: to.clone(), reason: reason.clone(), restarted });
+  Err(format!("新内核 {} 验证失败，已回滚到 {}: {}", version, to, reason))
+}
+
+/// 重启使用默认内核（未指定版本）的运行中实例，返回已重启的实例与第一个失败原因
+async fn restart_default_core_instances(registry: &State<'_, Shared<CoreRegistry>>) -> (Vec<String>, Option<String>) {
+  let cores = registry.lock().await.all();
+  let mut restarted = Vec::new();
+  let mut first_err = None;
+  for core in cores {
+    let (id, probe) = {
+      let mut mgr = core.lock().await;
+      if mgr.child.is_none() || mgr.core_version.is_some() {
+        continue;
+      }
+      (mgr.instance.clone(), mgr.restart().await)
+    };
+    match probe {
+      Ok(probe) => match manager::wait_ready(&core, probe).await {
+        Ok(()) => restarted.push(id),
+        Err(e) => {
+          first_err.get_or_insert(format!("实例 {} 启动失败: {}", id, e));
+        }
+      },
+      Err(e) => {
+        first_err.get_or_insert(format!("实例 {} 启动失败: {}", id, e));
+      }
+    }
+  }
+  (restarted, first_err)
+}
+
+/// 将 current 切回上一个版本，并以旧内核重启使用默认内核的运行中实例
+#[tauri::command]
+pub async fn rollback_core_version(
+  app: AppHandle,
+  registry: State<'_, Shared<CoreRegistry>>,
+  version_manager: State<'_, Shared<VersionManager>>,
+) -> Result<String, String> {
+  let (from, to, path) = version_manager.lock().await.rollback().map_err(|e| e.to_string())?;
+  log::info!("rollback_core_version {} -> {}", from, to);
+  registry.lock().await.set_default_core_path(path.clone()).await;
+  let (restarted, err) = restart_default_core_instances(&registry).await;
+  let _ = app.emit(CORE_ROLLED_BACK_EVENT, CoreRolledBackEvent { from, to, reason: "手动回滚".into(), restarted });
+  match err {
+    Some(e) => Err(e),
+    None => Ok(path.to_string_lossy().to_string()),
+  }
+}
+
+#[tauri::command]
+pub async fn get_previous_version(version_manager: State<'_, Shared<VersionManager>>) -> Result<Option<String>, String> {
+  Ok(version_manager.lock().await.previous_version())
+}
+
+/// 解析前端传入的渠道，未指定时使用设置中的默认渠道
+fn parse_channel(channel: Option<&str>, default: ReleaseChannel) -> Result<ReleaseChannel, String> {
+  match channel {
+    None => Ok(default),
+    Some("stable") => Ok(ReleaseChannel::Stable),
+    Some("dev") => Ok(ReleaseChannel::Dev),
+    Some(_) => Err("无效的渠道".into()),
+  }
+}
+
+#[tauri::command]
+pub async fn list_installed_versions(
+  registry: State<'_, Shared<CoreRegistry>>,
+  version_manager: State<'_, Shared<VersionManager>>,
+) -> Result<Vec<InstalledVersion>, String> {
+  let running = running_versions(&registry).await;
+  let mut list = version_manager.lock().await.list_installed();
+  list = tauri::async_runtime::spawn_blocking(move || {
+    probe_installed(&mut list);
+    list
+  })
+  .await
+  .map_err(|e| e.to_string())?;
+  for v in list.iter_mut() {
+    v.running = running.contains(&v.version);
+  }
+  Ok(list)
+}
+
+/// 将 current 切换到已安装的版本；使用默认内核的实例在下次启动时生效
+#[tauri::command]
+pub async fn set_current_version(
+  registry: State<'_, Shared<CoreRegistry>>,
+  version_manager: State<'_, Shared<VersionManager>>,
+  version: String,
+) -> Result<String, String> {
+  log::info!("set_current_version {}", version);
+  let path = version_manager.lock().await.set_current(&version).map_err(|e| e.to_string())?;
+  registry.lock().await.set_default_core_path(path.clone()).await;
+  Ok(path.to_string_lossy().to_string())
+}
+
+/// 删除已安装的版本；正在运行或为 current 的版本不可删除
+#[tauri::command]
+pub async fn remove_version(
+  registry: State<'_, Shared<CoreRegistry>>,
+  version_manager: State<'_, Shared<VersionManager>>,
+  version: String,
+) -> Result<(), String> {
+  log::info!("remove_version {}", version);
+  if running_versions(&registry).await.contains(&version) {
+    return Err(format!("内核版本 {} 正在运行，请先停止使用该版本的实例", version));
+  }
+  version_manager.lock().await.remove(&version).map_err(|e| e.to_string())
+}
+
+/// 各运行中实例所用的内核版本
+async fn running_versions(registry: &State<'_, Shared<CoreRegistry>>) -> Vec<String> {
+  let cores = registry.lock().await.all();
+  let mut versions = Vec::new();
+  for core in cores {
+    let mgr = core.lock().await;
+    if mgr.child.is_some() {
+      versions.extend(mgr.running_version());
+    }
+  }
+  versions
+}
+
 #[tauri::command]
 pub async fn get_core_install_dir(version_manager: State<'_, Shared<VersionManager>>) -> Result<String, String> {
   let vm = version_manager.lock().await;
@@ -1,3 +1,4 @@
 pub mod core_commands;
 pub mod config_commands;
+pub mod settings_commands;
 pub mod system_commands;
@@ -0,0 +1,62 @@
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::config::manager::ConfigManager;
+use crate::config::settings::{Settings, SettingsStore, SETTINGS_EVENT};
+use crate::core::registry::CoreRegistry;
+use crate::core::version::VersionManager;
+
+type Shared<T> = tauri::async_runtime::Mutex<T>;
+
+#[tauri::command]
+pub async fn get_settings(store: State<'_, Shared<SettingsStore>>) -> Result<Settings, String> {
+  let store = store.lock().await;
+  Ok(store.settings.clone())
+}
+
+/// 以 JSON 片段更新设置，例如 { "core": { "channel": "dev" } }；返回更新后的完整设置
+#[tauri::command]
+pub async fn update_settings(app: AppHandle, patch: serde_json::Value) -> Result<Settings, String> {
+  let settings = {
+    let store = app.state::<Shared<SettingsStore>>();
+    let mut store = store.lock().await;
+    store.update(patch)?
+  };
+  log::info!("settings updated");
+  publish(&app, &settings).await;
+  Ok(settings)
+}
+
+/// 修改设置并保存，随后应用到各管理器并推送 settings_changed；调用方不能持有管理器的锁
+pub(crate) async fn commit(app: &AppHandle, f: impl FnOnce(&mut Settings)) -> Result<Settings, String> {
+  let settings = {
+    let store = app.state::<Shared<SettingsStore>>();
+    let mut store = store.lock().await;
+    store.modify(f).map_err(|e| format!("保存设置失败: {}", e))?
+  };
+  publish(app, &settings).await;
+  Ok(settings)
+}
+
+/// 覆盖项要求自动生成 secret 而尚未生成时生成一个并保存，之后的运行时配置与预览都使用同一个值；
+/// 调用方不能持有管理器的锁
+pub(crate) async fn ensure_secret(app: &AppHandle) -> Result<(), String> {
+  let mut overrides = app.state::<Shared<ConfigManager>>().lock().await.overrides.clone();
+  if overrides.ensure_secret() {
+    let secret = overrides.secret;
+    commit(app, |s| {
+      // 并发生成时保留先保存的值
+      if s.profiles.overrides.secret.as_deref().map(str::is_empty).unwrap_or(true) {
+        s.profiles.overrides.secret = secret;
+      }
+    })
+    .await?;
+  }
+  Ok(())
+}
+
+async fn publish(app: &AppHandle, settings: &Settings) {
+  app.state::<Shared<ConfigManager>>().lock().await.apply_settings(settings);
+  app.state::<Shared<VersionManager>>().lock().await.apply_settings(settings);
+  app.state::<Shared<CoreRegistry>>().lock().await.apply_settings(settings).await;
+  let _ = app.emit(SETTINGS_EVENT, settings);
+}
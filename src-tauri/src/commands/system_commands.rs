@@ -4,7 +4,9 @@ use std::path::PathBuf;
 use std::process::Command;
 use tauri::State;
 
+use crate::commands::core_commands::instance_core;
 use crate::core::manager::CoreManager;
+use crate::core::registry::CoreRegistry;
 
 type Shared<T> = tauri::async_runtime::Mutex<T>;
 
@@ -19,8 +21,9 @@ pub struct TunHint {
 
 #[tauri::command]
 pub async fn check_tun_hint(
-  core_manager: State<'_, Shared<CoreManager>>,
+  registry: State<'_, Shared<CoreRegistry>>,
   config_path: String,
+  instance: Option<String>,
 ) -> Result<TunHint, String> {
   // 读取配置并判断是否启用 TUN
   let content = fs::read_to_string(&config_path).map_err(|e| e.to_string())?;
@@ -49,7 +52,8 @@ pub async fn check_tun_hint(
   {
     // 拿到已安装的内核路径
     let core_path: Option<PathBuf> = {
-      let mgr = core_manager.lock().await;
+      let core = instance_core(&registry, instance.as_deref()).await?;
+      let mgr = core.lock().await;
       mgr.core_path.clone()
     };
 
@@ -135,13 +139,8 @@ fn current_platform() -> &'static str {
 fn default_core_path_from_manager(mgr: &CoreManager) -> Option<PathBuf> {
   if let Some(p) = mgr.core_path.as_ref() { return Some(p.clone()); }
   let bin = if cfg!(target_os = "windows") { "mihomo.exe" } else { "mihomo" };
-  // 与 lib.rs 中的逻辑保持一致
-  let cores_dir = {
-    use super::super::core::version::VersionManager;
-    // 尝试与 VersionManager 相同的根目录
-    dirs::data_dir().unwrap_or_else(|| PathBuf::from(".")).join("mihomo-gui").join("cores")
-  };
-  let p = cores_dir.join("current").join(bin);
+  // 与 lib.rs 中的逻辑保持一致：使用设置中的 cores 目录
+  let p = mgr.cores_dir.as_ref()?.join("current").join(bin);
   if p.exists() { Some(p) } else { None }
 }
 
@@ -165,7 +164,7 @@ WantedBy=multi-user.target
 
 #[tauri::command]
 pub async fn install_systemd_service(
-  core_manager: State<'_, Shared<CoreManager>>,
+  registry: State<'_, Shared<CoreRegistry>>,
   config_path: String,
 ) -> Result<(), String> {
   #[cfg(not(target_os = "linux"))]
@@ -176,7 +175,8 @@ pub async fn install_systemd_service(
   #[cfg(target_os = "linux")]
   {
     let core_path = {
-      let mgr = core_manager.lock().await;
+      let core = instance_core(&registry, None).await?;
+      let mgr = core.lock().await;
       default_core_path_from_manager(&mgr).ok_or_else(|| "未找到内核路径，请先安装内核".to_string())?
     };
     // 复制 current 的二进制到 /usr/local/bin/mihomo（Fedora 推荐路径），并以此为 ExecStart
@@ -307,11 +307,15 @@ pub async fn request_privilege() -> Result<bool, String> {
 }
 
 #[tauri::command]
-pub async fn grant_tun_cap(core_manager: State<'_, Shared<CoreManager>>) -> Result<bool, String> {
+pub async fn grant_tun_cap(
+  registry: State<'_, Shared<CoreRegistry>>,
+  instance: Option<String>,
+) -> Result<bool, String> {
   #[cfg(target_os = "linux")]
   {
     let core_path = {
-      let mgr = core_manager.lock().await;
+      let core = instance_core(&registry, instance.as_deref()).await?;
+      let mgr = core.lock().await;
       mgr.core_path.clone().ok_or_else(|| "尚未安装或设置内核路径".to_string())?
     };
     let cmd = format!(
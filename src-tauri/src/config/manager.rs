@@ -1,21 +1,78 @@
 use serde::Serialize;
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::core::manager::LaunchSpec;
+
+use super::runtime::{self, RuntimeOverrides, RuntimePreview};
+use super::settings::Settings;
+use super::validator::{self, ConfigIssue};
+use super::workdir;
+
 #[derive(Debug, Clone, Default)]
 pub struct ConfigManager {
   pub config_dir: PathBuf,
   pub current_config: Option<PathBuf>,
+  /// 各 profile 的内核工作目录（mihomo -d）所在根目录
+  pub profiles_dir: PathBuf,
+  /// 各 profile 共享的地理数据目录
+  pub geodata_dir: PathBuf,
+  /// 是否通过符号链接在 profile 间共享地理数据
+  pub share_geodata: bool,
+  /// 合并了运行时覆盖项的生成配置所在目录
+  pub runtime_dir: PathBuf,
+  pub overrides: RuntimeOverrides,
+  /// 运行中的 profile 及其引用的本地文件变化时自动重载
+  pub auto_reload: bool,
+  /// 各 profile 指定的内核版本，键为 profile 名
+  pub core_versions: BTreeMap<String, String>,
 }
 
 impl ConfigManager {
-  pub fn new() -> io::Result<Self> {
-    let base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
-    let dir = base.join("mihomo-gui").join("configs");
+  /// 按设置中的目录与选项创建
+  pub fn new(settings: &Settings) -> io::Result<Self> {
+    fs::create_dir_all(&settings.paths.config_dir)?;
+    let mut mgr = Self::default();
+    mgr.apply_settings(settings);
+    mgr.current_config = settings.session.last_profile.clone();
+    Ok(mgr)
+  }
+
+  /// 应用设置变化；新的目录对之后的启动与导入生效
+  pub fn apply_settings(&mut self, settings: &Settings) {
+    self.config_dir = settings.paths.config_dir.clone();
+    self.profiles_dir = settings.paths.profiles_dir();
+    self.geodata_dir = settings.paths.geodata_dir();
+    self.runtime_dir = settings.paths.runtime_dir();
+    self.share_geodata = settings.profiles.share_geodata;
+    self.auto_reload = settings.profiles.auto_reload;
+    self.overrides = settings.profiles.overrides.clone();
+    self.core_versions = settings.profiles.core_versions.clone();
+  }
+
+  /// profile 对应的内核工作目录：<data_dir>/profiles/<name>
+  pub fn work_dir_for(&self, config_path: &Path) -> PathBuf {
+    self.profiles_dir.join(workdir::profile_name(config_path))
+  }
+
+  /// 创建工作目录，并按设置链接共享的地理数据
+  pub fn prepare_work_dir(&self, config_path: &Path) -> io::Result<PathBuf> {
+    let dir = self.work_dir_for(config_path);
     fs::create_dir_all(&dir)?;
-    Ok(Self { config_dir: dir, current_config: None })
+    if self.share_geodata {
+      if let Err(e) = workdir::link_geodata(&dir, &self.geodata_dir) {
+        log::warn!("链接共享地理数据失败: {}", e);
+      }
+    }
+    Ok(dir)
+  }
+
+  /// 清理 profile 的工作目录（cache.db、providers 等）；full 为 true 时整个目录重置
+  pub fn clean_work_dir(&self, config_path: &Path, full: bool) -> io::Result<Vec<PathBuf>> {
+    workdir::clean(&self.work_dir_for(config_path), full)
   }
 
   pub fn load_all_configs(&self) -> io::Result<Vec<ConfigInfo>> {
@@ -37,42 +94,53 @@ impl ConfigManager {
     Ok(result)
   }
 
-  pub fn validate(&self, config_path: &Path) -> io::Result<ValidationResult> {
-    let text = fs::read_to_string(config_path)?;
-    // 尝试用 serde_yaml 解析，不要求完整 schema
-    let yaml: serde_yaml::Value = match serde_yaml::from_str(&text) {
-      Ok(v) => v,
-      Err(_) => {
-        return Ok(ValidationResult {
-          is_valid: false,
-          warnings: vec!["YAML 解析失败".to_string()],
-          needs_privilege: false,
-        })
-      }
-    };
+  /// 生成实际传给内核的运行时配置：<data_dir>/runtime/<name>.yaml，原始文件保持不变
+  /// 需要自动生成的 secret 应已由 settings_commands::ensure_secret 生成并保存
+  pub fn write_runtime_config(&self, source: &Path) -> io::Result<PathBuf> {
+    let target = self.runtime_dir.join(format!("{}.yaml", workdir::profile_name(source)));
+    runtime::write(source, &self.overrides, &target)
+  }
 
-    let mut warnings = Vec::new();
-    let mut needs_privilege = false;
+  /// 准备 profile 工作目录并生成运行时配置，得到内核的启动参数
+  pub fn prepare_launch(&self, source: &Path) -> Result<LaunchSpec, String> {
+    let work_dir = self.prepare_work_dir(source).map_err(|e| e.to_string())?;
+    let runtime = self.write_runtime_config(source).map_err(|e| format!("生成运行时配置失败: {}", e))?;
+    Ok(LaunchSpec {
+      config: runtime,
+      source: source.to_path_buf(),
+      work_dir: Some(work_dir),
+      core_version: self.core_version_for(source),
+    })
+  }
 
-    // mixed-port
-    if yaml.get("mixed-port").is_none() && yaml.get("mixed_port").is_none() {
-      warnings.push("缺少 mixed-port".to_string());
-    }
-    // external-controller
-    if yaml.get("external-controller").is_none() && yaml.get("external_controller").is_none() {
-      warnings.push("缺少 external-controller".to_string());
-    }
-    // tun.enabled
-    if let Some(tun) = yaml.get("tun") {
-      if tun.get("enable").and_then(|v| v.as_bool()).unwrap_or(false)
-        || tun.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false)
-        || tun.get("enable").is_some() && tun.get("enable").unwrap() == &serde_yaml::Value::Bool(true)
-      {
-        needs_privilege = true;
+  /// profile 指定的内核版本，未指定时使用默认内核
+  pub fn core_version_for(&self, config_path: &Path) -> Option<String> {
+    self.core_versions.get(&workdir::profile_name(config_path)).cloned()
+  }
+
+  /// 需要监视的文件：profile 本身以及 type: file 的 rule-provider / proxy-provider
+  pub fn watched_files(&self, source: &Path) -> Vec<PathBuf> {
+    let mut files = vec![source.to_path_buf()];
+    let yaml: Option<serde_yaml::Value> = fs::read_to_string(source).ok().and_then(|t| serde_yaml::from_str(&t).ok());
+    let Some(yaml) = yaml else { return files };
+    // 内核以 -d 工作目录解析 provider 的相对路径
+    let home = self.work_dir_for(source);
+    for section in ["rule-providers", "proxy-providers"] {
+      let Some(providers) = yaml.get(section).and_then(|v| v.as_mapping()) else { continue };
+      for provider in providers.values() {
+        let is_file = provider.get("type").and_then(|v| v.as_str()) == Some("file");
+        let path = provider.get("path").and_then(|v| v.as_str());
+        if let (true, Some(p)) = (is_file, path) {
+          let p = PathBuf::from(p);
+          files.push(if p.is_absolute() { p } else { home.join(p) });
+        }
       }
     }
+    files
+  }
 
-    Ok(ValidationResult { is_valid: true, warnings, needs_privilege })
+  pub fn preview_runtime_config(&self, source: &Path) -> io::Result<RuntimePreview> {
+    runtime::preview(source, &self.overrides)
   }
 
   pub fn import_config(&self, source_path: &Path) -> io::Result<PathBuf> {
@@ -91,6 +159,58 @@ impl ConfigManager {
   }
 }
 
+/// 基础检查：YAML 能否解析，以及缺少的常用项与是否需要提权；不依赖 ConfigManager 的状态，可在锁外调用
+pub fn validate_file(config_path: &Path) -> io::Result<ValidationResult> {
+  let text = fs::read_to_string(config_path)?;
+  // 尝试用 serde_yaml 解析，不要求完整 schema
+  let yaml: serde_yaml::Value = match serde_yaml::from_str(&text) {
+    Ok(v) => v,
+    Err(_) => {
+      return Ok(ValidationResult {
+        is_valid: false,
+        warnings: vec!["YAML 解析失败".to_string()],
+        needs_privilege: false,
+        errors: Vec::new(),
+      })
+    }
+  };
+
+  let mut warnings = Vec::new();
+  let mut needs_privilege = false;
+
+  // mixed-port
+  if yaml.get("mixed-port").is_none() && yaml.get("mixed_port").is_none() {
+    warnings.push("缺少 mixed-port".to_string());
+  }
+  // external-controller
+  if yaml.get("external-controller").is_none() && yaml.get("external_controller").is_none() {
+    warnings.push("缺少 external-controller".to_string());
+  }
+  // tun.enabled
+  if let Some(tun) = yaml.get("tun") {
+    if tun.get("enable").and_then(|v| v.as_bool()).unwrap_or(false)
+      || tun.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false)
+      || tun.get("enable").is_some() && tun.get("enable").unwrap() == &serde_yaml::Value::Bool(true)
+    {
+      needs_privilege = true;
+    }
+  }
+
+  Ok(ValidationResult { is_valid: true, warnings, needs_privilege, errors: Vec::new() })
+}
+
+/// 在基础检查之上，再以 home（profile 工作目录）用已安装的内核测试配置（mihomo -t）；会阻塞到内核退出
+pub fn validate_with_core_in(config_path: &Path, core_path: &Path, home: &Path) -> io::Result<ValidationResult> {
+  let mut result = validate_file(config_path)?;
+  if !result.is_valid {
+    return Ok(result);
+  }
+  let test = validator::test_with_core(core_path, config_path, home)?;
+  result.is_valid = test.passed;
+  result.errors = test.errors;
+  Ok(result)
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ConfigInfo {
   pub name: String,
@@ -120,5 +240,7 @@ pub struct ValidationResult {
   pub is_valid: bool,
   pub warnings: Vec<String>,
   pub needs_privilege: bool,
+  /// 内核测试模式报告的错误
+  pub errors: Vec<ConfigIssue>,
 }
 
@@ -1,2 +1,6 @@
 pub mod manager;
-
+pub mod runtime;
+pub mod settings;
+pub mod validator;
+pub mod watcher;
+pub mod workdir;
@@ -0,0 +1,323 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+/// 应用层在启动时覆盖到 profile 之上的设置，不修改用户的原始配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuntimeOverrides {
+  /// 强制使用的 external-controller 地址
+  pub external_controller: Option<String>,
+  pub secret: Option<String>,
+  /// 未设置 secret 时自动生成一个
+  #[serde(default)]
+  pub generate_secret: bool,
+  pub allow_lan: Option<bool>,
+  pub log_level: Option<String>,
+  pub tun_enable: Option<bool>,
+}
+
+/// 一项被覆盖的配置，from/to 为 YAML 文本
+#[derive(Debug, Clone, Serialize)]
+pub struct OverrideChange {
+  pub key: String,
+  pub from: Option<String>,
+  pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimePreview {
+  pub merged: String,
+  pub changes: Vec<OverrideChange>,
+  /// 运行时配置相对原始配置的逐行差异（unified 格式）；原始配置按同样方式序列化，注释与排版差异不计入
+  pub diff: String,
+}
+
+impl RuntimeOverrides {
+  /// 需要时生成 secret 并保存在覆盖项中，保证多次启动使用同一个值；返回是否新生成，新值需由调用方保存到设置
+  pub fn ensure_secret(&mut self) -> bool {
+    if self.generate_secret && self.secret.as_deref().map(str::is_empty).unwrap_or(true) {
+      self.secret = Some(generate_secret());
+      return true;
+    }
+    false
+  }
+
+  /// 将覆盖项合并到配置上，返回实际发生的变化
+  pub fn apply(&self, yaml: &mut Value) -> Vec<OverrideChange> {
+    let mut changes = Vec::new();
+    if !yaml.is_mapping() {
+      *yaml = Value::Mapping(Mapping::new());
+    }
+    if let Some(v) = self.external_controller.as_ref() {
+      set_path(yaml, &["external-controller"], Value::String(v.clone()), &mut changes);
+    }
+    if let Some(v) = self.secret.as_ref() {
+      set_path(yaml, &["secret"], Value::String(v.clone()), &mut changes);
+    }
+    if let Some(v) = self.allow_lan {
+      set_path(yaml, &["allow-lan"], Value::Bool(v), &mut changes);
+    }
+    if let Some(v) = self.log_level.as_ref() {
+      set_path(yaml, &["log-level"], Value::String(v.clone()), &mut changes);
+    }
+    if let Some(v) = self.tun_enable {
+      set_path(yaml, &["tun", "enable"], Value::Bool(v), &mut changes);
+    }
+    changes
+  }
+}
+
+/// 读取 profile、合并覆盖项并生成预览，不修改覆盖项
+pub fn preview(source: &Path, overrides: &RuntimeOverrides) -> io::Result<RuntimePreview> {
+  let mut yaml = read_yaml(source)?;
+  let original = to_yaml(&yaml)?;
+  let changes = overrides.apply(&mut yaml);
+  let merged = to_yaml(&yaml)?;
+  let diff = line_diff(&original, &merged);
+  Ok(RuntimePreview { merged, changes, diff })
+}
+
+/// 生成合并后的运行时配置文件，返回其路径
+pub fn write(source: &Path, overrides: &RuntimeOverrides, target: &Path) -> io::Result<PathBuf> {
+  let mut yaml = read_yaml(source)?;
+  overrides.apply(&mut yaml);
+  if let Some(parent) = target.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  fs::write(target, to_yaml(&yaml)?)?;
+  Ok(target.to_path_buf())
+}
+
+fn read_yaml(path: &Path) -> io::Result<Value> {
+  let text = fs::read_to_string(path)?;
+  serde_yaml::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("YAML 解析失败: {}", e)))
+}
+
+fn to_yaml(value: &Value) -> io::Result<String> {
+  serde_yaml::to_string(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+fn set_path(root: &mut Value, path: &[&str], value: Value, changes: &mut Vec<OverrideChange>) {
+  let mut cur = root;
+  for key in &path[..path.len() - 1] {
+    let map = match cur {
+      Value::Mapping(m) => m,
+      _ => return,
+    };
+    let k = Value::String((*key).to_string());
+    let entry = map.entry(k).or_insert_with(|| Value::Mapping(Mapping::new()));
+    if !entry.is_mapping() {
+      *entry = Value::Mapping(Mapping::new());
+    }
+    cur = entry;
+  }
+  let Value::Mapping(map) = cur else { return };
+  let k = Value::String(path[path.len() - 1].to_string());
+  let old = map.insert(k, value.clone());
+  if old.as_ref() != Some(&value) {
+    changes.push(OverrideChange {
+      key: path.join("."),
+      from: old.as_ref().map(inline_yaml),
+      to: inline_yaml(&value),
+    });
+  }
+}
+
+/// 差异两侧保留的上下文行数
+const DIFF_CONTEXT: usize = 3;
+
+/// 超过该编辑距离时不再逐行比较，整体显示为删除与新增
+const DIFF_MAX_EDITS: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiffOp {
+  Equal,
+  Delete,
+  Insert,
+}
+
+/// 逐行比较，输出 unified 格式的差异；没有差异时为空
+fn line_diff(old: &str, new: &str) -> String {
+  let a: Vec<&str> = old.lines().collect();
+  let b: Vec<&str> = new.lines().collect();
+  let ops = diff_lines(&a, &b);
+
+  // 每个操作之前的行号（从 0 开始）
+  let mut positions = Vec::with_capacity(ops.len() + 1);
+  let (mut i, mut j) = (0, 0);
+  for (op, _) in &ops {
+    positions.push((i, j));
+    match op {
+      DiffOp::Equal => (i, j) = (i + 1, j + 1),
+      DiffOp::Delete => i += 1,
+      DiffOp::Insert => j += 1,
+    }
+  }
+  positions.push((i, j));
+
+  let mut out = String::new();
+  let mut idx = 0;
+  while let Some(first) = ops[idx..].iter().position(|(op, _)| *op != DiffOp::Equal).map(|p| p + idx) {
+    let start = first.saturating_sub(DIFF_CONTEXT);
+    // 相邻变化之间的相同行不超过两倍上下文时并入同一段
+    let mut end = first;
+    let mut equal_run = 0;
+    for (k, (op, _)) in ops.iter().enumerate().skip(first) {
+      if *op == DiffOp::Equal {
+        equal_run += 1;
+        if equal_run > DIFF_CONTEXT * 2 {
+          break;
+        }
+      } else {
+        equal_run = 0;
+        end = k;
+      }
+    }
+    let end = (end + 1 + DIFF_CONTEXT).min(ops.len());
+    let (old_start, new_start) = positions[start];
+    let (old_end, new_end) = positions[end];
+    out.push_str(&format!("@@ -{},{} +{},{} @@\n", old_start + 1, old_end - old_start, new_start + 1, new_end - new_start));
+    for (op, line) in &ops[start..end] {
+      let mark = match op {
+        DiffOp::Equal => ' ',
+        DiffOp::Delete => '-',
+        DiffOp::Insert => '+',
+      };
+      out.push(mark);
+      out.push_str(line);
+      out.push('\n');
+    }
+    idx = end;
+  }
+  out
+}
+
+/// Myers 差异算法，返回把 a 变为 b 的逐行操作
+fn diff_lines<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<(DiffOp, &'a str)> {
+  let (n, m) = (a.len() as isize, b.len() as isize);
+  let max = (n + m) as usize;
+  let offset = max as isize;
+  let mut v = vec![0isize; 2 * max + 2];
+  // 第 d 步开始前 v 在 k ∈ [-d-1, d+1] 上的快照（起始下标, 值），回溯只用到这一段
+  let mut trace: Vec<(usize, Vec<isize>)> = Vec::new();
+  let mut found = false;
+  for d in 0..=max.min(DIFF_MAX_EDITS) as isize {
+    let lo = (offset - d - 1).max(0) as usize;
+    let hi = ((offset + d + 1) as usize).min(v.len() - 1);
+    trace.push((lo, v[lo..=hi].to_vec()));
+    let mut k = -d;
+    while k <= d {
+      let i = (k + offset) as usize;
+      let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) { v[i + 1] } else { v[i - 1] + 1 };
+      let mut y = x - k;
+      while x < n && y < m && a[x as usize] == b[y as usize] {
+        x += 1;
+        y += 1;
+      }
+      v[i] = x;
+      if x >= n && y >= m {
+        found = true;
+        break;
+      }
+      k += 2;
+    }
+    if found {
+      break;
+    }
+  }
+  if !found {
+    return a.iter().map(|l| (DiffOp::Delete, *l)).chain(b.iter().map(|l| (DiffOp::Insert, *l))).collect();
+  }
+
+  let mut ops = Vec::new();
+  let (mut x, mut y) = (n, m);
+  for (d, (lo, v)) in trace.iter().enumerate().rev() {
+    let d = d as isize;
+    let at = |k: isize| v[(k + offset) as usize - lo];
+    let k = x - y;
+    let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+    let prev_x = at(prev_k);
+    let prev_y = prev_x - prev_k;
+    while x > prev_x && y > prev_y {
+      x -= 1;
+      y -= 1;
+      ops.push((DiffOp::Equal, a[x as usize]));
+    }
+    if d > 0 {
+      if x == prev_x {
+        ops.push((DiffOp::Insert, b[(y - 1) as usize]));
+      } else {
+        ops.push((DiffOp::Delete, a[(x - 1) as usize]));
+      }
+    }
+    x = prev_x;
+    y = prev_y;
+  }
+  ops.reverse();
+  ops
+}
+
+fn inline_yaml(v: &Value) -> String {
+  serde_yaml::to_string(v).map(|s| s.trim_end().to_string()).unwrap_or_default()
+}
+
+fn generate_secret() -> String {
+  let mut buf = [0u8; 16];
+  if getrandom::getrandom(&mut buf).is_err() {
+    // 极少数平台无系统随机源时退化为时间戳
+    let nanos = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_nanos();
+    buf.copy_from_slice(&nanos.to_le_bytes());
+  }
+  buf.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn line_diff_reports_changed_lines_with_context() {
+    let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+    let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+    assert_eq!(
+      line_diff(old, new),
+      "@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n@@ -9,3 +9,4 @@\n i\n j\n k\n+l\n"
+    );
+    assert_eq!(line_diff(old, old), "");
+    assert_eq!(line_diff("", "x\n"), "@@ -1,0 +1,1 @@\n+x\n");
+  }
+
+  #[test]
+  fn diff_lines_reconstructs_both_sides() {
+    let a = ["port: 7890", "mode: rule", "log-level: info", "dns:", "  enable: true"];
+    let b = ["port: 7890", "log-level: debug", "dns:", "  enable: true", "secret: abc"];
+    let ops = diff_lines(&a, &b);
+    let old: Vec<_> = ops.iter().filter(|(op, _)| *op != DiffOp::Insert).map(|(_, l)| *l).collect();
+    let new: Vec<_> = ops.iter().filter(|(op, _)| *op != DiffOp::Delete).map(|(_, l)| *l).collect();
+    assert_eq!(old, a);
+    assert_eq!(new, b);
+    assert_eq!(ops.iter().filter(|(op, _)| *op != DiffOp::Equal).count(), 4);
+  }
+
+  #[test]
+  fn preview_diffs_runtime_config_against_source_without_mutating_overrides() {
+    let dir = std::env::temp_dir().join(format!("mihomo-gui-runtime-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("profile.yaml");
+    fs::write(&source, "# comment\nmixed-port: 7890\nlog-level: info\n").unwrap();
+    let overrides = RuntimeOverrides { log_level: Some("debug".into()), generate_secret: true, ..Default::default() };
+
+    let preview = preview(&source, &overrides).unwrap();
+    assert!(overrides.secret.is_none());
+    assert_eq!(preview.changes.len(), 1);
+    assert!(preview.diff.contains("-log-level: info\n+log-level: debug\n"), "{}", preview.diff);
+    assert!(!preview.diff.contains("comment"), "{}", preview.diff);
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
@@ -0,0 +1,420 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::runtime::RuntimeOverrides;
+use crate::core::logfile::LogFileConfig;
+use crate::core::manager::{RestartConfig, DEFAULT_READY_TIMEOUT_MS, DEFAULT_STOP_GRACE_MS};
+use crate::core::mirror::Mirror;
+use crate::core::net::ProxySetting;
+use crate::core::version::{ReleaseChannel, DEFAULT_API_BASE, DEFAULT_KEEP_VERSIONS};
+
+/// 设置变化事件名
+pub const SETTINGS_EVENT: &str = "settings_changed";
+
+/// 当前设置文件的结构版本；结构变化时递增并在 MIGRATIONS 中追加一步迁移
+pub const SCHEMA_VERSION: u32 = 1 + MIGRATIONS.len() as u32;
+
+/// 启动时自动运行内核前的默认等待时长
+pub const DEFAULT_AUTO_START_DELAY_MS: u64 = 1000;
+
+/// 设置文件名，位于 <config_dir>/mihomo-gui/ 下
+const SETTINGS_FILE: &str = "settings.json";
+
+/// 应用设置，保存为 JSON；缺失的字段使用默认值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+  pub schema_version: u32,
+  pub paths: PathSettings,
+  pub core: CoreSettings,
+  pub network: NetworkSettings,
+  pub profiles: ProfileSettings,
+  pub log_file: LogFileConfig,
+  pub session: SessionSettings,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    Self {
+      schema_version: SCHEMA_VERSION,
+      paths: PathSettings::default(),
+      core: CoreSettings::default(),
+      network: NetworkSettings::default(),
+      profiles: ProfileSettings::default(),
+      log_file: LogFileConfig::default(),
+      session: SessionSettings::default(),
+    }
+  }
+}
+
+/// 应用使用的目录；修改后内核版本、profile 等目录立即生效，pid 文件与日志目录在下次启动应用时生效
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathSettings {
+  /// 导入的 profile 所在目录
+  pub config_dir: PathBuf,
+  /// 数据根目录：cores、profiles、geodata、runtime、logs 均位于其下
+  pub data_dir: PathBuf,
+}
+
+impl Default for PathSettings {
+  fn default() -> Self {
+    let config = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    let data = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+    Self { config_dir: config.join("mihomo-gui").join("configs"), data_dir: data.join("mihomo-gui") }
+  }
+}
+
+impl PathSettings {
+  pub fn cores_dir(&self) -> PathBuf {
+    self.data_dir.join("cores")
+  }
+
+  pub fn profiles_dir(&self) -> PathBuf {
+    self.data_dir.join("profiles")
+  }
+
+  pub fn geodata_dir(&self) -> PathBuf {
+    self.data_dir.join("geodata")
+  }
+
+  pub fn runtime_dir(&self) -> PathBuf {
+    self.data_dir.join("runtime")
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CoreSettings {
+  /// 检查更新与安装时默认使用的渠道
+  pub channel: ReleaseChannel,
+  /// 发布内核的 GitHub 仓库
+  pub repo: String,
+  /// GitHub API 地址，可指向兼容的镜像或本地模拟服务
+  pub api_base: String,
+  /// 获取发布信息与下载资产的途径，按顺序尝试
+  pub mirrors: Vec<Mirror>,
+  /// 升级后验证新内核（`mihomo -v` 与运行中实例的就绪探测），失败时自动回滚到之前的版本
+  pub auto_rollback: bool,
+  /// 每个渠道保留的最近安装版本数；current、回滚目标、profile 指定与运行中的版本始终保留
+  pub keep_versions: usize,
+  /// 每次安装成功后按保留策略清理旧版本
+  pub auto_gc: bool,
+  pub restart: RestartConfig,
+  pub stop_grace_ms: u64,
+  pub ready_timeout_ms: u64,
+}
+
+impl Default for CoreSettings {
+  fn default() -> Self {
+    Self {
+      channel: ReleaseChannel::Stable,
+      repo: "MetaCubeX/mihomo".into(),
+      api_base: DEFAULT_API_BASE.into(),
+      mirrors: vec![Mirror::Direct],
+      auto_rollback: true,
+      keep_versions: DEFAULT_KEEP_VERSIONS,
+      auto_gc: true,
+      restart: RestartConfig::default(),
+      stop_grace_ms: DEFAULT_STOP_GRACE_MS,
+      ready_timeout_ms: DEFAULT_READY_TIMEOUT_MS,
+    }
+  }
+}
+
+/// 出站网络请求的设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+  /// 检查更新、获取发布列表与下载内核时使用的代理
+  pub proxy: ProxySetting,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileSettings {
+  /// 是否通过符号链接在 profile 间共享地理数据
+  pub share_geodata: bool,
+  /// 运行中的 profile 及其引用的本地文件变化时自动重载
+  pub auto_reload: bool,
+  pub overrides: RuntimeOverrides,
+  /// 各 profile 指定的内核版本，键为 profile 名
+  pub core_versions: BTreeMap<String, String>,
+}
+
+impl Default for ProfileSettings {
+  fn default() -> Self {
+    Self { share_geodata: true, auto_reload: true, overrides: RuntimeOverrides::default(), core_versions: BTreeMap::new() }
+  }
+}
+
+/// 跨会话保存的运行状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionSettings {
+  /// 上次启动内核所用的 profile
+  pub last_profile: Option<PathBuf>,
+  /// 上次运行的内核版本
+  pub last_version: Option<String>,
+  /// 退出应用时内核是否在运行（用户手动停止后为 false）
+  pub was_running: bool,
+  /// 应用启动时若上次内核在运行，则以上次的 profile 自动启动
+  pub auto_start: bool,
+  pub auto_start_delay_ms: u64,
+}
+
+impl Default for SessionSettings {
+  fn default() -> Self {
+    Self {
+      last_profile: None,
+      last_version: None,
+      was_running: false,
+      auto_start: false,
+      auto_start_delay_ms: DEFAULT_AUTO_START_DELAY_MS,
+    }
+  }
+}
+
+impl SessionSettings {
+  /// 应启动时自动运行的 profile
+  pub fn auto_start_profile(&self) -> Option<&Path> {
+    if !self.auto_start || !self.was_running {
+      return None;
+    }
+    self.last_profile.as_deref().filter(|p| p.exists())
+  }
+}
+
+/// 设置文件的读写
+#[derive(Debug)]
+pub struct SettingsStore {
+  path: PathBuf,
+  pub settings: Settings,
+}
+
+impl SettingsStore {
+  /// 设置文件固定位于 <config_dir>/mihomo-gui/settings.json，不受 paths 设置影响
+  pub fn default_path() -> PathBuf {
+    dirs::config_dir().unwrap_or_else(|| PathBuf::from(".")).join("mihomo-gui").join(SETTINGS_FILE)
+  }
+
+  /// 读取设置并迁移到当前版本；文件不存在时使用默认值，无法解析时备份原文件后使用默认值。
+  /// 文件来自更新版本的应用时先备份，之后的保存会丢弃未识别的字段
+  pub fn load(path: PathBuf) -> Self {
+    let mut value = match fs::read_to_string(&path) {
+      Ok(text) => match serde_json::from_str::<Value>(&text) {
+        Ok(v) if v.is_object() => v,
+        _ => {
+          let backup = path.with_extension("json.bak");
+          log::warn!("设置文件无法解析，已备份到 {}", backup.display());
+          let _ = fs::rename(&path, &backup);
+          Value::Object(Map::new())
+        }
+      },
+      Err(_) => Value::Object(Map::new()),
+    };
+
+    let from = schema_version(&value);
+    if from > SCHEMA_VERSION {
+      let backup = path.with_extension(format!("json.v{}.bak", from));
+      match fs::copy(&path, &backup) {
+        Ok(_) => log::warn!("设置文件版本 {} 高于当前支持的 {}，未识别的字段将被忽略，原文件已备份到 {}", from, SCHEMA_VERSION, backup.display()),
+        Err(e) => log::warn!("设置文件版本 {} 高于当前支持的 {}，备份到 {} 失败: {}", from, SCHEMA_VERSION, backup.display(), e),
+      }
+    }
+    let migrated = migrate(&mut value, MIGRATIONS);
+    let settings = serde_json::from_value(value).unwrap_or_else(|e| {
+      log::warn!("设置内容无效，使用默认设置: {}", e);
+      Settings::default()
+    });
+    let store = Self { path, settings };
+    if migrated {
+      log::info!("设置已从版本 {} 迁移到 {}", from, SCHEMA_VERSION);
+      if let Err(e) = store.save() {
+        log::warn!("保存迁移后的设置失败: {}", e);
+      }
+    }
+    store
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// 原子保存：写入同目录临时文件并落盘后再替换
+  pub fn save(&self) -> io::Result<()> {
+    if let Some(parent) = self.path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    let tmp = self.path.with_extension("json.tmp");
+    {
+      let mut file = File::create(&tmp)?;
+      file.write_all(serde_json::to_string_pretty(&self.settings)?.as_bytes())?;
+      file.sync_all()?;
+    }
+    fs::rename(&tmp, &self.path)
+  }
+
+  /// 修改设置并保存
+  pub fn modify(&mut self, f: impl FnOnce(&mut Settings)) -> io::Result<Settings> {
+    f(&mut self.settings);
+    self.settings.schema_version = SCHEMA_VERSION;
+    self.save()?;
+    Ok(self.settings.clone())
+  }
+
+  /// 以 JSON 片段深度合并到当前设置，校验后保存
+  pub fn update(&mut self, patch: Value) -> Result<Settings, String> {
+    let mut value = serde_json::to_value(&self.settings).map_err(|e| e.to_string())?;
+    merge(&mut value, patch);
+    let mut settings: Settings = serde_json::from_value(value).map_err(|e| format!("设置无效: {}", e))?;
+    settings.schema_version = SCHEMA_VERSION;
+    self.settings = settings;
+    self.save().map_err(|e| format!("保存设置失败: {}", e))?;
+    Ok(self.settings.clone())
+  }
+}
+
+/// 一步迁移，原地修改设置对象
+type Migration = fn(&mut Map<String, Value>);
+
+/// 第 i 项把版本 i + 1 的设置迁移到版本 i + 2
+const MIGRATIONS: &[Migration] = &[];
+
+/// 版本从 1 开始；没有 schema_version 的文件（如手动创建）视为版本 1
+fn schema_version(value: &Value) -> u32 {
+  value.get("schema_version").and_then(Value::as_u64).unwrap_or(1).max(1) as u32
+}
+
+/// 依次执行 migrations 迁移到版本 migrations.len() + 1，返回是否有变化
+fn migrate(value: &mut Value, migrations: &[Migration]) -> bool {
+  let mut version = schema_version(value);
+  let Some(map) = value.as_object_mut() else { return false };
+  let mut changed = false;
+  while (version as usize) <= migrations.len() {
+    migrations[version as usize - 1](map);
+    version += 1;
+    changed = true;
+  }
+  if changed {
+    map.insert("schema_version".into(), Value::from(version));
+  }
+  changed
+}
+
+fn merge(target: &mut Value, patch: Value) {
+  match (target, patch) {
+    (Value::Object(t), Value::Object(p)) => {
+      for (k, v) in p {
+        merge(t.entry(k).or_insert(Value::Null), v);
+      }
+    }
+    (t, p) => *t = p,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  /// v1 → v2：core_path 改为 core.path
+  fn nest_core_path(map: &mut Map<String, Value>) {
+    if let Some(path) = map.remove("core_path") {
+      map.insert("core".into(), json!({ "path": path }));
+    }
+  }
+
+  /// v2 → v3：新增 core.channel
+  fn add_channel(map: &mut Map<String, Value>) {
+    if let Some(Value::Object(core)) = map.get_mut("core") {
+      core.entry("channel").or_insert(json!("stable"));
+    }
+  }
+
+  const TEST_MIGRATIONS: &[Migration] = &[nest_core_path, add_channel];
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mihomo-gui-settings-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn migrates_step_by_step_from_any_older_version() {
+    let mut v1 = json!({ "core_path": "/opt/mihomo" });
+    assert!(migrate(&mut v1, TEST_MIGRATIONS));
+    assert_eq!(v1, json!({ "schema_version": 3, "core": { "path": "/opt/mihomo", "channel": "stable" } }));
+
+    let mut v2 = json!({ "schema_version": 2, "core": { "path": "/opt/mihomo", "channel": "dev" } });
+    assert!(migrate(&mut v2, TEST_MIGRATIONS));
+    assert_eq!(v2["schema_version"], 3);
+    assert_eq!(v2["core"]["channel"], "dev");
+
+    let mut current = json!({ "schema_version": 3, "core": {} });
+    assert!(!migrate(&mut current, TEST_MIGRATIONS));
+    assert_eq!(current, json!({ "schema_version": 3, "core": {} }));
+  }
+
+  #[test]
+  fn schema_version_defaults_to_one() {
+    assert_eq!(schema_version(&json!({})), 1);
+    assert_eq!(schema_version(&json!({ "schema_version": 0 })), 1);
+    assert_eq!(schema_version(&json!({ "schema_version": "2" })), 1);
+    assert_eq!(schema_version(&json!({ "schema_version": 4 })), 4);
+  }
+
+  #[test]
+  fn newer_schema_is_left_alone_and_backed_up() {
+    let mut newer = json!({ "schema_version": 9, "future": true });
+    assert!(!migrate(&mut newer, TEST_MIGRATIONS));
+    assert_eq!(newer["future"], true);
+
+    let dir = temp_dir("newer");
+    let path = dir.join(SETTINGS_FILE);
+    let text = json!({ "schema_version": SCHEMA_VERSION + 1, "future": { "kept": true } }).to_string();
+    fs::write(&path, &text).unwrap();
+    let mut store = SettingsStore::load(path.clone());
+    assert_eq!(fs::read_to_string(&path).unwrap(), text, "loading must not rewrite a newer file");
+
+    store.modify(|s| s.session.was_running = true).unwrap();
+    let backup = path.with_extension(format!("json.v{}.bak", SCHEMA_VERSION + 1));
+    assert_eq!(fs::read_to_string(backup).unwrap(), text);
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn save_replaces_the_file_atomically() {
+    let dir = temp_dir("save");
+    let path = dir.join(SETTINGS_FILE);
+    fs::write(&path, "{ \"schema_version\": 1, \"session\": { \"was_running\": true } }").unwrap();
+
+    let mut store = SettingsStore::load(path.clone());
+    assert!(store.settings.session.was_running);
+    store.modify(|s| s.session.was_running = false).unwrap();
+
+    assert!(!path.with_extension("json.tmp").exists());
+    let saved: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved["schema_version"], SCHEMA_VERSION);
+    assert_eq!(saved["session"]["was_running"], false);
+    assert!(!SettingsStore::load(path).settings.session.was_running);
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn unreadable_file_is_backed_up() {
+    let dir = temp_dir("corrupt");
+    let path = dir.join(SETTINGS_FILE);
+    fs::write(&path, "{ not json").unwrap();
+    let store = SettingsStore::load(path.clone());
+    assert_eq!(store.settings.schema_version, SCHEMA_VERSION);
+    assert_eq!(fs::read_to_string(path.with_extension("json.bak")).unwrap(), "{ not json");
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
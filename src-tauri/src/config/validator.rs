@@ -0,0 +1,97 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::Serialize;
+
+use crate::core::logs::logfmt_value;
+
+/// 内核测试模式报告的单条错误
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigIssue {
+  pub message: String,
+  pub line: Option<usize>,
+  pub column: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreTestResult {
+  pub passed: bool,
+  pub errors: Vec<ConfigIssue>,
+  /// 内核的原始输出
+  pub output: String,
+}
+
+impl CoreTestResult {
+  /// 汇总为一段可直接展示的错误文本
+  pub fn summary(&self) -> String {
+    self
+      .errors
+      .iter()
+      .map(|e| match (e.line, e.column) {
+        (Some(l), Some(c)) => format!("第 {} 行第 {} 列: {}", l, c, e.message),
+        (Some(l), None) => format!("第 {} 行: {}", l, e.message),
+        _ => e.message.clone(),
+      })
+      .collect::<Vec<_>>()
+      .join("\n")
+  }
+}
+
+/// 测试模式使用的临时工作目录，避免在用户配置目录下生成缓存文件
+pub fn default_test_home() -> PathBuf {
+  std::env::temp_dir().join("mihomo-gui-test")
+}
+
+/// 使用已安装的内核以测试模式检查配置：mihomo -t -d <home> -f <config>
+pub fn test_with_core(core: &Path, config: &Path, home: &Path) -> io::Result<CoreTestResult> {
+  if !core.exists() {
+    return Err(io::Error::new(io::ErrorKind::NotFound, "内核可执行文件不存在"));
+  }
+  fs::create_dir_all(home)?;
+  let out = Command::new(core)
+    .arg("-t")
+    .arg("-d")
+    .arg(home)
+    .arg("-f")
+    .arg(config)
+    .stdin(Stdio::null())
+    .output()?;
+  let output = format!("{}{}", String::from_utf8_lossy(&out.stdout), String::from_utf8_lossy(&out.stderr));
+  let passed = out.status.success() && output.contains("test is successful");
+  let mut errors = parse_errors(&output);
+  if !passed && errors.is_empty() {
+    errors.push(ConfigIssue { message: format!("内核测试未通过 ({})", out.status), line: None, column: None });
+  }
+  Ok(CoreTestResult { passed, errors, output })
+}
+
+/// 提取错误级别日志与 "test failed" 行，并解析其中的 "line N" / "column N"
+fn parse_errors(output: &str) -> Vec<ConfigIssue> {
+  let mut errors = Vec::new();
+  for raw in output.lines() {
+    let line = raw.trim();
+    if line.is_empty() {
+      continue;
+    }
+    let level = logfmt_value(line, "level").unwrap_or_default().to_ascii_lowercase();
+    let is_error = matches!(level.as_str(), "error" | "fatal" | "panic") || line.contains("test failed");
+    if !is_error {
+      continue;
+    }
+    let message = logfmt_value(line, "msg").unwrap_or_else(|| line.to_string());
+    errors.push(ConfigIssue {
+      line: number_after(&message, "line "),
+      column: number_after(&message, "column "),
+      message,
+    });
+  }
+  errors
+}
+
+fn number_after(text: &str, key: &str) -> Option<usize> {
+  let idx = text.find(key)?;
+  let digits: String = text[idx + key.len()..].chars().take_while(|c| c.is_ascii_digit()).collect();
+  digits.parse().ok()
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::manager::{self as config_manager, ConfigManager, ValidationResult};
+use crate::commands::settings_commands;
+use crate::core::manager;
+use crate::core::registry::{CoreRegistry, SharedCore};
+
+type Shared<T> = tauri::async_runtime::Mutex<T>;
+
+/// 配置校验失败事件名
+pub const CONFIG_INVALID_EVENT: &str = "config_invalid";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// 最后一次变化后静默这么久才处理，合并编辑器的多次写入
+const DEBOUNCE: Duration = Duration::from_millis(800);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigInvalidEvent {
+  pub instance: String,
+  pub path: String,
+  pub validation: Option<ValidationResult>,
+  pub message: String,
+}
+
+type Stamp = Option<(SystemTime, u64)>;
+
+#[derive(Default)]
+struct WatchState {
+  source: Option<PathBuf>,
+  stamps: HashMap<PathBuf, Stamp>,
+  changed: Vec<PathBuf>,
+  last_change: Option<Instant>,
+}
+
+impl WatchState {
+  fn reset(&mut self, source: Option<PathBuf>, files: Vec<PathBuf>) {
+    self.source = source;
+    self.stamps = files
+      .into_iter()
+      .map(|f| {
+        let s = stamp(&f);
+        (f, s)
+      })
+      .collect();
+    self.changed.clear();
+    self.last_change = None;
+  }
+
+  fn poll(&mut self) {
+    for (path, old) in self.stamps.iter_mut() {
+      let new = stamp(path);
+      if new != *old {
+        *old = new;
+        if !self.changed.contains(path) {
+          self.changed.push(path.clone());
+        }
+        self.last_change = Some(Instant::now());
+      }
+    }
+  }
+}
+
+/// 启动后台任务：监视各运行中实例的 profile 及其本地 provider 文件，去抖后校验并自动重载内核
+pub fn spawn(app: AppHandle) {
+  tauri::async_runtime::spawn(async move {
+    let mut states: HashMap<String, WatchState> = HashMap::new();
+    loop {
+      tokio::time::sleep(POLL_INTERVAL).await;
+      let cfg_state = app.state::<Shared<ConfigManager>>();
+      let enabled = cfg_state.lock().await.auto_reload;
+      let cores = app.state::<Shared<CoreRegistry>>().lock().await.all();
+      let mut seen = Vec::with_capacity(cores.len());
+      for core in cores {
+        let (instance, source) = {
+          let cm = core.lock().await;
+          (cm.instance.clone(), cm.child.as_ref().and(cm.source_config.clone()))
+        };
+        let Some(source) = source.filter(|_| enabled) else { continue };
+        seen.push(instance.clone());
+        let state = states.entry(instance).or_default();
+        if state.source.as_ref() != Some(&source) {
+          let files = cfg_state.lock().await.watched_files(&source);
+          state.reset(Some(source), files);
+          continue;
+        }
+
+        state.poll();
+        let settled = state.last_change.map(|t| t.elapsed() >= DEBOUNCE).unwrap_or(false);
+        if state.changed.is_empty() || !settled {
+          continue;
+        }
+        let changed: Vec<PathBuf> = state.changed.drain(..).collect();
+        state.last_change = None;
+        log::info!("config files changed: {:?}", changed);
+        handle_change(&app, &core, &source, &changed).await;
+        // provider 列表可能随配置改变，重新收集
+        let files = cfg_state.lock().await.watched_files(&source);
+        state.reset(Some(source), files);
+      }
+      states.retain(|id, _| seen.contains(id));
+    }
+  });
+}
+
+async fn handle_change(app: &AppHandle, core: &SharedCore, source: &Path, changed: &[PathBuf]) {
+  let cfg_state = app.state::<Shared<ConfigManager>>();
+  let version = cfg_state.lock().await.core_version_for(source);
+  let (instance, core_path) = {
+    let cm = core.lock().await;
+    (cm.instance.clone(), cm.resolve_core(version.as_deref()).ok())
+  };
+
+  for path in changed {
+    // mihomo -t 可能耗时数秒，释放 ConfigManager 锁后在阻塞线程中执行
+    let home = match (path == source, core_path.as_ref()) {
+      (true, Some(_)) => match cfg_state.lock().await.prepare_work_dir(path) {
+        Ok(home) => Some(home),
+        Err(e) => {
+          emit_invalid(app, &instance, path, None, format!("准备工作目录失败: {}", e));
+          return;
+        }
+      },
+      _ => None,
+    };
+    let (path_owned, is_source, core) = (path.clone(), path == source, core_path.clone());
+    let validation = tauri::async_runtime::spawn_blocking(move || {
+      let path = path_owned.as_path();
+      if !is_source {
+        return validate_provider_file(path).map(|_| None);
+      }
+      match (core, home) {
+        (Some(core), Some(home)) => config_manager::validate_with_core_in(path, &core, &home),
+        _ => config_manager::validate_file(path),
+      }
+      .map(Some)
+    })
+    .await
+    .unwrap_or_else(|e| Err(std::io::Error::other(e.to_string())));
+    match validation {
+      Ok(Some(result)) if !result.is_valid => {
+        emit_invalid(app, &instance, path, Some(result), "配置未通过校验，内核保持当前配置".into());
+        return;
+      }
+      Err(e) => {
+        emit_invalid(app, &instance, path, None, format!("文件校验失败: {}", e));
+        return;
+      }
+      _ => {}
+    }
+  }
+
+  if let Err(e) = settings_commands::ensure_secret(app).await {
+    emit_invalid(app, &instance, source, None, e);
+    return;
+  }
+  let spec = match cfg_state.lock().await.prepare_launch(source) {
+    Ok(spec) => spec,
+    Err(e) => {
+      emit_invalid(app, &instance, source, None, e);
+      return;
+    }
+  };
+  match manager::reload(core, spec).await {
+    Ok(report) => log::info!("auto reload {} via {:?} ({:?})", instance, report.method, report.reason),
+    Err(e) => emit_invalid(app, &instance, source, None, e),
+  }
+}
+
+/// provider 文件仅在为 YAML 时检查能否解析；其他格式交由内核判断
+fn validate_provider_file(path: &Path) -> std::io::Result<()> {
+  let is_yaml = path.extension().and_then(|e| e.to_str()).map(|e| matches!(e, "yaml" | "yml")).unwrap_or(false);
+  let text = fs::read_to_string(path)?;
+  if is_yaml {
+    serde_yaml::from_str::<serde_yaml::Value>(&text)
+      .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+  }
+  Ok(())
+}
+
+fn emit_invalid(app: &AppHandle, instance: &str, path: &Path, validation: Option<ValidationResult>, message: String) {
+  log::warn!("config invalid {} ({}): {}", path.display(), instance, message);
+  let _ = app.emit(
+    CONFIG_INVALID_EVENT,
+    ConfigInvalidEvent { instance: instance.to_string(), path: path.to_string_lossy().to_string(), validation, message },
+  );
+}
+
+fn stamp(path: &Path) -> Stamp {
+  let meta = fs::metadata(path).ok()?;
+  Some((meta.modified().ok()?, meta.len()))
+}
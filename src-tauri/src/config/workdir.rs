@@ -0,0 +1,125 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+/// mihomo 在工作目录中使用的地理数据文件
+pub const GEODATA_FILES: &[&str] = &[
+  "geoip.dat",
+  "geosite.dat",
+  "geoip.metadb",
+  "country.mmdb",
+  "Country.mmdb",
+  "GeoLite2-ASN.mmdb",
+  "ASN.mmdb",
+];
+
+/// 由配置文件名（含扩展名，a.yaml 与 a.yml 互不相同）得到 profile 名，用作工作目录、运行时配置与内核版本的键；
+/// 文件名含有需要替换的字符时附加原文件名的哈希，避免 a b.yaml 与 a_b.yaml 共用同一目录
+pub fn profile_name(config_path: &Path) -> String {
+  let file_name = config_path
+    .file_name()
+    .map(|s| s.to_string_lossy().to_string())
+    .unwrap_or_else(|| "default".into());
+  let safe: String = file_name
+    .chars()
+    .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+    .collect();
+  if safe == file_name && !safe.starts_with('.') {
+    return safe;
+  }
+  let digest = Sha256::digest(file_name.as_bytes());
+  format!("{}-{}", safe.trim_start_matches('.'), digest[..4].iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
+
+/// 将共享目录中的地理数据链接到工作目录；工作目录中已下载而共享目录缺失的文件会先被收集到共享目录
+pub fn link_geodata(work_dir: &Path, shared: &Path) -> io::Result<()> {
+  fs::create_dir_all(shared)?;
+  for name in GEODATA_FILES {
+    let local = work_dir.join(name);
+    let common = shared.join(name);
+    let local_meta = fs::symlink_metadata(&local).ok();
+    let is_link = local_meta.as_ref().map(|m| m.file_type().is_symlink()).unwrap_or(false);
+
+    if let Some(meta) = local_meta.as_ref() {
+      if meta.is_file() && !is_link && !common.exists() {
+        fs::copy(&local, &common)?;
+      }
+    }
+    if !common.exists() || is_link {
+      continue;
+    }
+    if local_meta.is_some() {
+      fs::remove_file(&local)?;
+    }
+    link_file(&common, &local)?;
+  }
+  Ok(())
+}
+
+fn link_file(src: &Path, dst: &Path) -> io::Result<()> {
+  #[cfg(unix)]
+  {
+    std::os::unix::fs::symlink(src, dst)
+  }
+  #[cfg(windows)]
+  {
+    // 创建符号链接需要管理员或开发者模式，失败则回退到复制
+    if std::os::windows::fs::symlink_file(src, dst).is_ok() {
+      return Ok(());
+    }
+    fs::copy(src, dst).map(|_| ())
+  }
+}
+
+/// 清理工作目录：full 为 true 时删除整个目录，否则仅保留地理数据
+pub fn clean(work_dir: &Path, full: bool) -> io::Result<Vec<PathBuf>> {
+  let mut removed = Vec::new();
+  if !work_dir.exists() {
+    return Ok(removed);
+  }
+  if full {
+    fs::remove_dir_all(work_dir)?;
+    removed.push(work_dir.to_path_buf());
+    return Ok(removed);
+  }
+  for entry in fs::read_dir(work_dir)? {
+    let path = entry?.path();
+    let keep = path
+      .file_name()
+      .and_then(|n| n.to_str())
+      .map(|n| GEODATA_FILES.contains(&n))
+      .unwrap_or(false);
+    if keep {
+      continue;
+    }
+    let meta = fs::symlink_metadata(&path)?;
+    if meta.is_dir() {
+      fs::remove_dir_all(&path)?;
+    } else {
+      fs::remove_file(&path)?;
+    }
+    removed.push(path);
+  }
+  Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn profile_name_keeps_extension_and_disambiguates_replaced_chars() {
+    assert_eq!(profile_name(Path::new("/p/a.yaml")), "a.yaml");
+    assert_ne!(profile_name(Path::new("/p/a.yaml")), profile_name(Path::new("/p/a.yml")));
+
+    let spaced = profile_name(Path::new("/p/a b.yaml"));
+    assert!(spaced.starts_with("a_b.yaml-"), "{spaced}");
+    assert_ne!(spaced, profile_name(Path::new("/p/a_b.yaml")));
+    assert_ne!(spaced, profile_name(Path::new("/p/a?b.yaml")));
+    assert_eq!(spaced, profile_name(Path::new("/other/a b.yaml")));
+
+    assert!(!profile_name(Path::new("/p/.hidden.yaml")).starts_with('.'));
+  }
+}
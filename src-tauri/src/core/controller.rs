@@ -0,0 +1,103 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+
+/// mihomo external-controller 的访问信息
+#[derive(Debug, Clone)]
+pub struct Controller {
+  pub base_url: String,
+  pub secret: Option<String>,
+}
+
+impl Controller {
+  /// 从配置文件读取 external-controller 与 secret；未配置时返回 None
+  pub fn from_config(path: &Path) -> Option<Self> {
+    let text = fs::read_to_string(path).ok()?;
+    let yaml: serde_yaml::Value = serde_yaml::from_str(&text).ok()?;
+    Self::from_yaml(&yaml)
+  }
+
+  pub fn from_yaml(yaml: &serde_yaml::Value) -> Option<Self> {
+    let addr = yaml
+      .get("external-controller")
+      .or_else(|| yaml.get("external_controller"))
+      .and_then(|v| v.as_str())?
+      .trim();
+    if addr.is_empty() {
+      return None;
+    }
+    let secret = yaml
+      .get("secret")
+      .and_then(|v| v.as_str())
+      .filter(|s| !s.is_empty())
+      .map(|s| s.to_string());
+    Some(Self { base_url: format!("http://{}", connect_addr(addr)), secret })
+  }
+
+  /// 访问本机控制器的客户端，不走任何代理
+  pub(crate) fn client(&self) -> Result<reqwest::Client> {
+    Ok(
+      reqwest::Client::builder()
+        .no_proxy()
+        .timeout(Duration::from_secs(2))
+        .build()?,
+    )
+  }
+
+  pub(crate) fn request(&self, client: &reqwest::Client, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+    let req = client.request(method, format!("{}{}", self.base_url, path));
+    match self.secret.as_ref() {
+      Some(s) => req.bearer_auth(s),
+      None => req,
+    }
+  }
+
+  /// 调用 GET /version，返回内核版本号
+  pub async fn version(&self) -> Result<String> {
+    let client = self.client()?;
+    let resp = self
+      .request(&client, reqwest::Method::GET, "/version")
+      .send()
+      .await
+      .context("连接 external-controller 失败")?;
+    let status = resp.status();
+    if !status.is_success() {
+      return Err(anyhow!("external-controller HTTP 状态错误: {}", status));
+    }
+    let body: serde_json::Value = resp.json().await.context("解析 /version 响应失败")?;
+    Ok(body.get("version").and_then(|v| v.as_str()).unwrap_or_default().to_string())
+  }
+
+  /// 调用 PUT /configs?force=true，以配置内容热重载内核（避免内核对 path 的安全目录限制）
+  pub async fn reload_payload(&self, payload: &str) -> Result<()> {
+    let client = reqwest::Client::builder().no_proxy().timeout(Duration::from_secs(30)).build()?;
+    let resp = self
+      .request(&client, reqwest::Method::PUT, "/configs?force=true")
+      .json(&serde_json::json!({ "path": "", "payload": payload }))
+      .send()
+      .await
+      .context("连接 external-controller 失败")?;
+    let status = resp.status();
+    if !status.is_success() {
+      let body = resp.text().await.unwrap_or_default();
+      return Err(anyhow!("热重载失败: {} - {}", status, body.trim()));
+    }
+    Ok(())
+  }
+}
+
+/// 将监听地址转换为可连接地址：空主机或通配地址改为回环地址
+fn connect_addr(listen: &str) -> String {
+  let (host, port) = match listen.rsplit_once(':') {
+    Some((h, p)) => (h.trim_start_matches('[').trim_end_matches(']'), p),
+    None => return listen.to_string(),
+  };
+  match host {
+    "" | "0.0.0.0" | "*" => format!("127.0.0.1:{}", port),
+    "::" => format!("[::1]:{}", port),
+    h if h.contains(':') => format!("[{}]:{}", h, port),
+    h => format!("{}:{}", h, port),
+  }
+}
@@ -0,0 +1,401 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::future::Future;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use futures_util::StreamExt;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+
+/// 安装进度事件名
+pub const INSTALL_PROGRESS_EVENT: &str = "version_install_progress";
+
+/// 连接中断后的最大重试次数
+pub const MAX_ATTEMPTS: u32 = 5;
+
+/// 两次重试之间的等待时间，逐次递增
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// 检查取消标记的间隔
+const CANCEL_POLL: Duration = Duration::from_millis(100);
+
+/// 下载被取消
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("下载已取消")
+  }
+}
+
+impl std::error::Error for Cancelled {}
+
+pub fn is_cancelled(err: &anyhow::Error) -> bool {
+  err.is::<Cancelled>()
+}
+
+/// 进行中的下载；同一时间只允许一个安装任务
+#[derive(Debug, Default)]
+pub struct Downloads {
+  active: Option<(String, Arc<AtomicBool>)>,
+  next_id: u64,
+}
+
+impl Downloads {
+  /// 登记新的下载并返回其 id 与取消标记；已有下载进行中时拒绝
+  pub fn begin(&mut self) -> Result<(String, Arc<AtomicBool>), String> {
+    if let Some((id, _)) = self.active.as_ref() {
+      return Err(format!("已有下载正在进行（{}），请等待完成或先取消", id));
+    }
+    self.next_id += 1;
+    let id = format!("download-{}", self.next_id);
+    let cancel = Arc::new(AtomicBool::new(false));
+    self.active = Some((id.clone(), cancel.clone()));
+    Ok((id, cancel))
+  }
+
+  pub fn cancel(&self, id: &str) -> Result<(), String> {
+    match self.active.as_ref() {
+      Some((active, flag)) if active == id => {
+        flag.store(true, Ordering::SeqCst);
+        Ok(())
+      }
+      _ => Err(format!("下载 {} 不存在或已结束", id)),
+    }
+  }
+
+  pub fn finish(&mut self, id: &str) {
+    if self.active.as_ref().map(|(active, _)| active == id).unwrap_or(false) {
+      self.active = None;
+    }
+  }
+
+  pub fn active(&self) -> Option<String> {
+    self.active.as_ref().map(|(id, _)| id.clone())
+  }
+}
+
+/// 下载完成的文件
+#[derive(Debug, Clone)]
+pub struct Downloaded {
+  pub path: PathBuf,
+  pub size: u64,
+  /// 十六进制小写的 SHA256
+  pub sha256: String,
+}
+
+/// 将 url 流式下载到 part 文件，边写边计算 SHA256。
+/// part 文件已存在（上次中断）或传输中断时以 Range 请求续传；服务端忽略 Range 时清空文件从头下载。
+/// cancel 被置位后尽快以 Cancelled 错误返回，part 文件由调用方清理。on_progress 参数为（已接收字节数，总字节数）
+pub async fn download_to_file(
+  client: &reqwest::Client,
+  url: &str,
+  part: &Path,
+  cancel: &AtomicBool,
+  mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<Downloaded> {
+  if let Some(parent) = part.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  let (mut hasher, mut received) = hash_existing(part)?;
+  let mut file = OpenOptions::new().create(true).append(true).open(part).with_context(|| format!("打开 {} 失败", part.display()))?;
+  let mut total: Option<u64> = None;
+  let mut attempt = 0;
+
+  loop {
+    attempt += 1;
+    let mut req = client.get(url);
+    if received > 0 {
+      log::info!("resuming download of {} from byte {}", url, received);
+      req = req.header(RANGE, format!("bytes={}-", received));
+    }
+    let resp = match or_cancel(cancel, req.send()).await? {
+      Ok(r) => r,
+      Err(e) if attempt < MAX_ATTEMPTS => {
+        log::warn!("download request failed (attempt {}): {}", attempt, e);
+        or_cancel(cancel, tokio::time::sleep(RETRY_BACKOFF * attempt)).await?;
+        continue;
+      }
+      Err(e) => return Err(anyhow!(e).context("下载请求失败")),
+    };
+
+    match resp.status() {
+      StatusCode::PARTIAL_CONTENT => {
+        let (start, len) = parse_content_range(resp.headers().get(CONTENT_RANGE).and_then(|v| v.to_str().ok()))
+          .ok_or_else(|| anyhow!("服务端返回的 Content-Range 无效"))?;
+        if start != received {
+          // 起点与本地不一致，无法续传，从头开始
+          log::warn!("range start {} does not match local size {}, restarting", start, received);
+          (hasher, received) = restart(&mut file)?;
+          continue;
+        }
+        total = len.or(total);
+      }
+      StatusCode::OK => {
+        if received > 0 {
+          log::warn!("server ignored Range header, restarting download");
+          (hasher, received) = restart(&mut file)?;
+        }
+        total = resp.content_length();
+      }
+      StatusCode::RANGE_NOT_SATISFIABLE if received > 0 => {
+        // 本地文件不小于远端（可能来自其他版本的同名资产），从头下载
+        log::warn!("range not satisfiable at byte {}, restarting", received);
+        (hasher, received) = restart(&mut file)?;
+        continue;
+      }
+      status => {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(anyhow!("下载 HTTP 状态错误: {} - {}", status, body.chars().take(200).collect::<String>()));
+      }
+    }
+
+    let mut stream = resp.bytes_stream();
+    let mut interrupted = None;
+    while let Some(chunk) = or_cancel(cancel, stream.next()).await? {
+      match chunk {
+        Ok(chunk) => {
+          file.write_all(&chunk)?;
+          hasher.update(&chunk);
+          received += chunk.len() as u64;
+          on_progress(received, total);
+        }
+        Err(e) => {
+          interrupted = Some(e.to_string());
+          break;
+        }
+      }
+    }
+    file.flush()?;
+
+    let complete = interrupted.is_none() && total.map(|t| received >= t).unwrap_or(true);
+    if complete {
+      file.sync_all()?;
+      let sha256 = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+      return Ok(Downloaded { path: part.to_path_buf(), size: received, sha256 });
+    }
+    let reason = interrupted.unwrap_or_else(|| format!("连接提前关闭（{}/{} 字节）", received, total.unwrap_or(0)));
+    if attempt >= MAX_ATTEMPTS {
+      return Err(anyhow!("下载中断且重试 {} 次后仍失败: {}", attempt, reason));
+    }
+    log::warn!("download interrupted at byte {} (attempt {}): {}", received, attempt, reason);
+    or_cancel(cancel, tokio::time::sleep(RETRY_BACKOFF * attempt)).await?;
+  }
+}
+
+/// 等待 fut 完成，期间 cancel 被置位则返回 Cancelled
+async fn or_cancel<F: Future>(cancel: &AtomicBool, fut: F) -> Result<F::Output> {
+  let watch = async {
+    while !cancel.load(Ordering::SeqCst) {
+      tokio::time::sleep(CANCEL_POLL).await;
+    }
+  };
+  tokio::select! {
+    biased;
+    _ = watch => Err(Cancelled.into()),
+    out = fut => Ok(out),
+  }
+}
+
+/// 读取已有的 part 文件，返回其哈希状态与长度
+fn hash_existing(part: &Path) -> Result<(Sha256, u64)> {
+  let mut hasher = Sha256::new();
+  let mut file = match File::open(part) {
+    Ok(f) => f,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((hasher, 0)),
+    Err(e) => return Err(e.into()),
+  };
+  let mut buf = vec![0u8; 64 * 1024];
+  let mut len = 0u64;
+  loop {
+    let n = file.read(&mut buf)?;
+    if n == 0 {
+      break;
+    }
+    hasher.update(&buf[..n]);
+    len += n as u64;
+  }
+  Ok((hasher, len))
+}
+
+fn restart(file: &mut File) -> Result<(Sha256, u64)> {
+  file.set_len(0)?;
+  Ok((Sha256::new(), 0))
+}
+
+/// 解析 "bytes <start>-<end>/<total>"，total 为 * 时返回 None
+fn parse_content_range(value: Option<&str>) -> Option<(u64, Option<u64>)> {
+  let range = value?.trim().strip_prefix("bytes")?.trim();
+  let (span, total) = range.split_once('/')?;
+  let start = span.split_once('-')?.0.trim().parse().ok()?;
+  Some((start, total.trim().parse().ok()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::net::{build_client, ProxyRoute};
+  use crate::core::test_server::{Reply, Request, TestServer};
+  use crate::core::version::verify_sha256_hex;
+
+  fn body() -> Vec<u8> {
+    (0..1000u32).map(|i| (i * 7 % 251) as u8).collect()
+  }
+
+  fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+  }
+
+  /// 测试专用的 part 文件路径，prefix 非空时预先写入
+  fn part_file(name: &str, prefix: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mihomo-gui-download-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let part = dir.join(format!("{name}.part"));
+    let _ = fs::remove_file(&part);
+    if !prefix.is_empty() {
+      fs::write(&part, prefix).unwrap();
+    }
+    part
+  }
+
+  fn range_start(req: &Request) -> Option<usize> {
+    req.header("range")?.strip_prefix("bytes=")?.strip_suffix('-')?.parse().ok()
+  }
+
+  /// 遵循 Range 的服务端：从请求的偏移返回 206，没有 Range 时返回 200
+  fn ranged(body: &[u8], from: Option<usize>) -> Reply {
+    match from {
+      Some(start) => Reply::new(206, body[start..].to_vec())
+        .header("Content-Range", &format!("bytes {}-{}/{}", start, body.len() - 1, body.len())),
+      None => Reply::new(200, body.to_vec()),
+    }
+  }
+
+  async fn download(server: &TestServer, part: &Path) -> Result<Downloaded> {
+    let client = build_client(&ProxyRoute::Direct).unwrap();
+    download_to_file(&client, &server.url("/mihomo.gz"), part, &AtomicBool::new(false), |_, _| {}).await
+  }
+
+  fn ranges(server: &TestServer) -> Vec<Option<usize>> {
+    server.requests().iter().map(range_start).collect()
+  }
+
+  #[tokio::test]
+  async fn resumes_existing_part_from_its_offset() {
+    let full = body();
+    let part = part_file("resume", &full[..300]);
+    let served = full.clone();
+    let server = TestServer::start(move |req| ranged(&served, range_start(req)));
+
+    let done = download(&server, &part).await.unwrap();
+    assert_eq!(ranges(&server), [Some(300)]);
+    assert_eq!(fs::read(&part).unwrap(), full);
+    assert_eq!((done.size, done.sha256), (full.len() as u64, sha256_hex(&full)));
+  }
+
+  #[tokio::test]
+  async fn resumes_after_connection_cut_mid_body() {
+    let full = body();
+    let part = part_file("cut", &[]);
+    let served = full.clone();
+    // 每次只送出 400 字节就断开，第三次请求才能收完
+    let server = TestServer::start(move |req| ranged(&served, range_start(req)).cut_after(400));
+
+    let done = download(&server, &part).await.unwrap();
+    assert_eq!(ranges(&server), [None, Some(400), Some(800)]);
+    assert_eq!(fs::read(&part).unwrap(), full);
+    assert_eq!(done.sha256, sha256_hex(&full));
+  }
+
+  #[tokio::test]
+  async fn restarts_when_server_ignores_range() {
+    let full = body();
+    let part = part_file("ignored-range", b"stale bytes from another build");
+    let served = full.clone();
+    let server = TestServer::start(move |_| Reply::new(200, served.clone()));
+
+    let done = download(&server, &part).await.unwrap();
+    assert_eq!(ranges(&server), [Some(30)]);
+    assert_eq!(fs::read(&part).unwrap(), full);
+    assert_eq!((done.size, done.sha256), (full.len() as u64, sha256_hex(&full)));
+  }
+
+  #[tokio::test]
+  async fn restarts_on_range_not_satisfiable() {
+    let full = body();
+    let mut oversized = full.clone();
+    oversized.extend_from_slice(b"trailing");
+    let part = part_file("unsatisfiable", &oversized);
+    let served = full.clone();
+    let server = TestServer::start(move |req| match range_start(req) {
+      Some(start) if start >= served.len() => {
+        Reply::new(416, Vec::new()).header("Content-Range", &format!("bytes */{}", served.len()))
+      }
+      from => ranged(&served, from),
+    });
+
+    let done = download(&server, &part).await.unwrap();
+    assert_eq!(ranges(&server), [Some(oversized.len()), None]);
+    assert_eq!(fs::read(&part).unwrap(), full);
+    assert_eq!(done.sha256, sha256_hex(&full));
+  }
+
+  #[tokio::test]
+  async fn sha256_mismatch_is_detected() {
+    let full = body();
+    let checksums = format!("{}  mihomo.gz\n", sha256_hex(&full));
+
+    // part 文件的前缀已损坏：续传得到的文件长度正确但哈希与校验列表不符
+    let mut corrupt = full[..300].to_vec();
+    corrupt[0] ^= 0xff;
+    let part = part_file("corrupt", &corrupt);
+    let served = full.clone();
+    let server = TestServer::start(move |req| ranged(&served, range_start(req)));
+
+    let done = download(&server, &part).await.unwrap();
+    assert_eq!(done.size, full.len() as u64);
+    let err = verify_sha256_hex(&done.sha256, &checksums).unwrap_err();
+    assert!(err.to_string().contains("SHA256"), "{err}");
+
+    // 清除后重新下载的文件通过校验
+    fs::remove_file(&part).unwrap();
+    let done = download(&server, &part).await.unwrap();
+    verify_sha256_hex(&done.sha256, &checksums).unwrap();
+  }
+
+  #[tokio::test]
+  async fn gives_up_after_max_attempts() {
+    let full = body();
+    let part = part_file("exhausted", &[]);
+    // 忽略 Range 且总在中途断开：每次都从头开始，始终无法完成
+    let server = TestServer::start(move |_| Reply::new(200, full.clone()).cut_after(100));
+
+    let err = download(&server, &part).await.unwrap_err();
+    assert!(err.to_string().contains(&format!("重试 {} 次", MAX_ATTEMPTS)), "{err}");
+    assert_eq!(server.requests().len(), MAX_ATTEMPTS as usize);
+  }
+
+  #[tokio::test]
+  async fn reports_http_errors_without_retrying() {
+    let part = part_file("not-found", &[]);
+    let server = TestServer::start(|_| Reply::new(404, "Not Found"));
+    let err = download(&server, &part).await.unwrap_err();
+    assert!(err.to_string().contains("404"), "{err}");
+    assert_eq!(server.requests().len(), 1);
+  }
+
+  #[test]
+  fn parses_content_range() {
+    assert_eq!(parse_content_range(Some("bytes 300-999/1000")), Some((300, Some(1000))));
+    assert_eq!(parse_content_range(Some("bytes 0-9/*")), Some((0, None)));
+    assert_eq!(parse_content_range(Some("items 0-9/10")), None);
+    assert_eq!(parse_content_range(None), None);
+  }
+}
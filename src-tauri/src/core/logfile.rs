@@ -0,0 +1,353 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::logs::LogEntry;
+use super::util::unix_millis;
+
+/// 当前写入的日志文件名；轮转后的文件为 core-<毫秒时间戳>-<序号>.log[.gz]
+const ACTIVE_LOG: &str = "core.log";
+
+/// 压缩中的临时文件后缀，完成后重命名为 .log.gz
+const GZ_PARTIAL: &str = ".gz.partial";
+
+/// 轮转文件的排序键：（毫秒时间戳, 同一毫秒内的序号）；当前文件视为最新
+type LogKey = (u64, u64);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogFileConfig {
+  /// 单个文件超过该大小后轮转
+  pub max_bytes: u64,
+  /// 保留的已轮转文件数
+  pub keep_files: usize,
+  /// 轮转后是否 gzip 压缩
+  pub compress: bool,
+}
+
+impl Default for LogFileConfig {
+  fn default() -> Self {
+    Self { max_bytes: 5 * 1024 * 1024, keep_files: 5, compress: false }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogFileInfo {
+  pub name: String,
+  pub path: String,
+  pub size: u64,
+  pub modified: String,
+  pub compressed: bool,
+}
+
+#[derive(Debug, Default)]
+struct Sink {
+  dir: Option<PathBuf>,
+  config: LogFileConfig,
+  file: Option<File>,
+  size: u64,
+  /// 轮转序号，保证同一毫秒内的多次轮转文件名不同
+  seq: u64,
+}
+
+/// 内核输出的持久化日志，按大小轮转
+#[derive(Debug, Default)]
+pub struct LogFileWriter {
+  inner: Mutex<Sink>,
+}
+
+impl LogFileWriter {
+  /// 设置日志目录；未设置时不写入磁盘
+  pub fn set_dir(&self, dir: PathBuf) {
+    let mut sink = self.lock();
+    sink.file = None;
+    sink.dir = Some(dir);
+  }
+
+  pub fn dir(&self) -> Option<PathBuf> {
+    self.lock().dir.clone()
+  }
+
+  pub fn config(&self) -> LogFileConfig {
+    self.lock().config.clone()
+  }
+
+  pub fn set_config(&self, config: LogFileConfig) {
+    self.lock().config = config;
+  }
+
+  /// 以 "<毫秒时间戳> [<stream>] <原始行>" 的格式追加一行
+  pub fn append(&self, entry: &LogEntry, raw: &str) {
+    let mut sink = self.lock();
+    if let Err(e) = write_line(&mut sink, entry, raw) {
+      // 出错时丢弃句柄，下次写入重新打开
+      sink.file = None;
+      log::error!(target: "mihomo-core", "写入日志文件失败: {}", e);
+    }
+  }
+
+  pub fn list(&self) -> io::Result<Vec<LogFileInfo>> {
+    let Some(dir) = self.dir() else { return Ok(Vec::new()) };
+    let mut files = log_files(&dir)?;
+    files.sort_by_key(|(key, _)| std::cmp::Reverse(*key));
+    let mut result = Vec::new();
+    for (_, path) in files {
+      let meta = fs::metadata(&path)?;
+      let modified = meta.modified().unwrap_or(UNIX_EPOCH).duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+      result.push(LogFileInfo {
+        name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        path: path.to_string_lossy().to_string(),
+        size: meta.len(),
+        modified: format!("{}", modified),
+        compressed: is_gz(&path),
+      });
+    }
+    Ok(result)
+  }
+
+  /// 导出 [from_ms, to_ms] 时间段内的日志行到目标文件，返回导出的行数
+  pub fn export(&self, target: &Path, from_ms: Option<u64>, to_ms: Option<u64>) -> io::Result<usize> {
+    let Some(dir) = self.dir() else { return Ok(0) };
+    // 先刷新当前文件，确保导出包含最新内容
+    if let Some(file) = self.lock().file.as_mut() {
+      file.flush()?;
+    }
+    let mut files = log_files(&dir)?;
+    files.sort_by_key(|(key, _)| *key);
+    let mut out = io::BufWriter::new(File::create(target)?);
+    let mut count = 0;
+    for (_, path) in files {
+      let reader: Box<dyn Read> = if is_gz(&path) {
+        Box::new(flate2::read::GzDecoder::new(File::open(&path)?))
+      } else {
+        Box::new(File::open(&path)?)
+      };
+      for line in BufReader::new(reader).lines() {
+        let line = line?;
+        let ts = line.split_whitespace().next().and_then(|t| t.parse::<u64>().ok());
+        let Some(ts) = ts else { continue };
+        if from_ms.map(|f| ts < f).unwrap_or(false) || to_ms.map(|t| ts > t).unwrap_or(false) {
+          continue;
+        }
+        writeln!(out, "{}", line)?;
+        count += 1;
+      }
+    }
+    out.flush()?;
+    Ok(count)
+  }
+
+  /// 删除已轮转的日志；指定 older_than 时仅删除早于该时长的文件。返回删除的文件数
+  pub fn purge(&self, older_than: Option<Duration>) -> io::Result<usize> {
+    let Some(dir) = self.dir() else { return Ok(0) };
+    let cutoff = older_than.map(|d| unix_millis().saturating_sub(d.as_millis() as u64));
+    let mut removed = 0;
+    for ((ts, _), path) in log_files(&dir)? {
+      if path.file_name().map(|n| n == ACTIVE_LOG).unwrap_or(false) {
+        continue;
+      }
+      if cutoff.map(|c| ts < c).unwrap_or(true) {
+        fs::remove_file(&path)?;
+        removed += 1;
+      }
+    }
+    Ok(removed)
+  }
+
+  fn lock(&self) -> std::sync::MutexGuard<'_, Sink> {
+    self.inner.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+fn write_line(sink: &mut Sink, entry: &LogEntry, raw: &str) -> io::Result<()> {
+  let Some(dir) = sink.dir.clone() else { return Ok(()) };
+  if sink.file.is_none() {
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(ACTIVE_LOG);
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    sink.size = file.metadata()?.len();
+    sink.file = Some(file);
+  }
+  let line = format!("{} [{}] {}\n", entry.timestamp, entry.stream.as_str(), raw);
+  if let Some(file) = sink.file.as_mut() {
+    file.write_all(line.as_bytes())?;
+  }
+  sink.size += line.len() as u64;
+  if sink.size >= sink.config.max_bytes {
+    rotate(sink, &dir)?;
+  }
+  Ok(())
+}
+
+/// 在锁内只做重命名与清理，压缩交给后台线程，避免阻塞读取内核输出的线程；返回压缩线程
+fn rotate(sink: &mut Sink, dir: &Path) -> io::Result<Option<JoinHandle<()>>> {
+  sink.file = None;
+  sink.size = 0;
+  let ts = unix_millis();
+  let rotated = loop {
+    sink.seq += 1;
+    let path = dir.join(format!("core-{}-{}.log", ts, sink.seq));
+    if !path.exists() && !path.with_extension("log.gz").exists() {
+      break path;
+    }
+  };
+  fs::rename(dir.join(ACTIVE_LOG), &rotated)?;
+
+  // 仅保留最新的 keep_files 个轮转文件
+  let mut rotated_files: Vec<(LogKey, PathBuf)> = log_files(dir)?
+    .into_iter()
+    .filter(|(_, p)| p.file_name().map(|n| n != ACTIVE_LOG).unwrap_or(false))
+    .collect();
+  rotated_files.sort_by_key(|(key, _)| std::cmp::Reverse(*key));
+  for (_, path) in rotated_files.into_iter().skip(sink.config.keep_files) {
+    let _ = fs::remove_file(&path);
+    let _ = fs::remove_file(path.with_extension("log.gz"));
+  }
+
+  if !sink.config.compress {
+    return Ok(None);
+  }
+  Ok(Some(thread::spawn(move || {
+    if let Err(e) = compress(&rotated) {
+      log::warn!(target: "mihomo-core", "压缩日志 {} 失败: {}", rotated.display(), e);
+    }
+  })))
+}
+
+/// 先写入临时文件，完成后替换为 .log.gz 并删除原文件；原文件在压缩期间被清理时放弃
+fn compress(rotated: &Path) -> io::Result<()> {
+  let gz_path = rotated.with_extension("log.gz");
+  let partial = PathBuf::from(format!("{}{}", rotated.display(), GZ_PARTIAL));
+  let result = (|| {
+    let mut encoder = flate2::write::GzEncoder::new(File::create(&partial)?, flate2::Compression::default());
+    io::copy(&mut File::open(rotated)?, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    if !rotated.exists() {
+      return Err(io::Error::new(io::ErrorKind::NotFound, "日志已被清理"));
+    }
+    fs::rename(&partial, &gz_path)?;
+    fs::remove_file(rotated)
+  })();
+  if result.is_err() {
+    let _ = fs::remove_file(&partial);
+  }
+  result
+}
+
+/// 列出目录中的日志文件及其排序键；压缩尚未完成时同一文件的 .log 与 .log.gz 并存，只列出 .log
+fn log_files(dir: &Path) -> io::Result<Vec<(LogKey, PathBuf)>> {
+  let mut result = Vec::new();
+  if !dir.exists() {
+    return Ok(result);
+  }
+  for entry in fs::read_dir(dir)? {
+    let path = entry?.path();
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+    if name == ACTIVE_LOG {
+      result.push(((u64::MAX, 0), path));
+      continue;
+    }
+    if name.ends_with(".log.gz") && path.with_extension("").exists() {
+      continue;
+    }
+    if let Some(key) = parse_key(name) {
+      result.push((key, path));
+    }
+  }
+  Ok(result)
+}
+
+/// 解析 core-<ts>-<seq>.log[.gz]，兼容没有序号的 core-<ts>.log[.gz]
+fn parse_key(name: &str) -> Option<LogKey> {
+  let stem = name.strip_prefix("core-")?;
+  let stem = stem.strip_suffix(".log.gz").or_else(|| stem.strip_suffix(".log"))?;
+  match stem.split_once('-') {
+    Some((ts, seq)) => Some((ts.parse().ok()?, seq.parse().ok()?)),
+    None => Some((stem.parse().ok()?, 0)),
+  }
+}
+
+fn is_gz(path: &Path) -> bool {
+  path.extension().map(|e| e == "gz").unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mihomo-gui-logfile-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn sink(dir: &Path, compress: bool) -> Sink {
+    Sink {
+      dir: Some(dir.to_path_buf()),
+      config: LogFileConfig { keep_files: 10, compress, ..Default::default() },
+      ..Default::default()
+    }
+  }
+
+  fn rotate_with(sink: &mut Sink, dir: &Path, content: &str) -> Option<JoinHandle<()>> {
+    fs::write(dir.join(ACTIVE_LOG), content).unwrap();
+    rotate(sink, dir).unwrap()
+  }
+
+  #[test]
+  fn parses_keys_with_and_without_seq() {
+    assert_eq!(parse_key("core-1700-3.log"), Some((1700, 3)));
+    assert_eq!(parse_key("core-1700-3.log.gz"), Some((1700, 3)));
+    assert_eq!(parse_key("core-1700.log"), Some((1700, 0)));
+    assert_eq!(parse_key("core-1700.log.gz.partial"), None);
+    assert_eq!(parse_key("core-x-1.log"), None);
+  }
+
+  #[test]
+  fn rapid_rotations_keep_every_file() {
+    let dir = temp_dir("rapid");
+    let mut sink = sink(&dir, false);
+    for i in 0..5 {
+      assert!(rotate_with(&mut sink, &dir, &format!("line {}\n", i)).is_none());
+    }
+    let mut files = log_files(&dir).unwrap();
+    files.sort_by_key(|(key, _)| *key);
+    assert_eq!(files.len(), 5);
+    let contents: Vec<String> = files.iter().map(|(_, p)| fs::read_to_string(p).unwrap()).collect();
+    assert_eq!(contents, (0..5).map(|i| format!("line {}\n", i)).collect::<Vec<_>>());
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn compresses_in_background_and_prunes() {
+    let dir = temp_dir("compress");
+    let mut sink = sink(&dir, true);
+    sink.config.keep_files = 2;
+    for i in 0..3 {
+      rotate_with(&mut sink, &dir, &format!("line {}\n", i)).expect("compress thread").join().unwrap();
+    }
+    let mut files = log_files(&dir).unwrap();
+    files.sort_by_key(|(key, _)| *key);
+    assert_eq!(files.len(), 2);
+    assert!(files.iter().all(|(_, p)| is_gz(p)));
+    let mut text = String::new();
+    flate2::read::GzDecoder::new(File::open(&files[1].1).unwrap()).read_to_string(&mut text).unwrap();
+    assert_eq!(text, "line 2\n");
+    assert!(fs::read_dir(&dir).unwrap().flatten().all(|e| !e.file_name().to_string_lossy().ends_with(GZ_PARTIAL)));
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn skips_compression_of_pruned_file() {
+    let dir = temp_dir("pruned");
+    let rotated = dir.join("core-1-1.log");
+    assert!(compress(&rotated).is_err());
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,242 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::logfile::LogFileWriter;
+use super::util::unix_millis;
+
+/// 新日志行推送事件名
+pub const CORE_LOG_EVENT: &str = "core_log";
+
+/// 内存中保留的最大日志条数
+pub const DEFAULT_LOG_CAPACITY: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+  Debug,
+  Info,
+  Warning,
+  Error,
+}
+
+impl LogLevel {
+  pub fn parse(s: &str) -> Option<Self> {
+    match s.to_ascii_lowercase().as_str() {
+      "debug" => Some(LogLevel::Debug),
+      "info" => Some(LogLevel::Info),
+      "warn" | "warning" => Some(LogLevel::Warning),
+      "error" | "fatal" | "panic" => Some(LogLevel::Error),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+  Stdout,
+  Stderr,
+}
+
+impl LogStream {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      LogStream::Stdout => "stdout",
+      LogStream::Stderr => "stderr",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+  /// 单调递增序号，便于前端去重与增量拉取
+  pub seq: u64,
+  /// 毫秒级 Unix 时间戳
+  pub timestamp: u64,
+  pub level: LogLevel,
+  pub stream: LogStream,
+  pub message: String,
+}
+
+/// core_log 事件负载，附带产生该日志的实例 id
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreLogEvent {
+  pub instance: String,
+  #[serde(flatten)]
+  pub entry: LogEntry,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogPage {
+  pub entries: Vec<LogEntry>,
+  /// 过滤后的总条数
+  pub total: usize,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct LogQuery {
+  /// 最低日志级别
+  pub level: Option<LogLevel>,
+  /// 子串过滤（不区分大小写）
+  pub keyword: Option<String>,
+  /// 跳过最新的若干条，用于向前翻页
+  pub offset: Option<usize>,
+  pub limit: Option<usize>,
+}
+
+#[derive(Debug)]
+struct Ring {
+  entries: VecDeque<LogEntry>,
+  capacity: usize,
+  next_seq: u64,
+}
+
+/// 内核 stdout/stderr 的有界环形缓冲区
+#[derive(Debug)]
+pub struct LogBuffer {
+  inner: Mutex<Ring>,
+}
+
+impl Default for LogBuffer {
+  fn default() -> Self {
+    Self::with_capacity(DEFAULT_LOG_CAPACITY)
+  }
+}
+
+impl LogBuffer {
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self {
+      inner: Mutex::new(Ring { entries: VecDeque::with_capacity(capacity), capacity, next_seq: 0 }),
+    }
+  }
+
+  pub fn push(&self, stream: LogStream, line: &str) -> LogEntry {
+    let (level, message) = parse_line(line, stream);
+    let mut ring = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = LogEntry { seq: ring.next_seq, timestamp: unix_millis(), level, stream, message };
+    ring.next_seq += 1;
+    if ring.entries.len() >= ring.capacity {
+      ring.entries.pop_front();
+    }
+    ring.entries.push_back(entry.clone());
+    entry
+  }
+
+  /// 按级别与关键字过滤，返回从新到旧偏移 offset 后的 limit 条（结果按时间正序）
+  pub fn query(&self, q: &LogQuery) -> LogPage {
+    let ring = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+    let keyword = q.keyword.as_ref().map(|k| k.to_lowercase()).filter(|k| !k.is_empty());
+    let matched: Vec<&LogEntry> = ring
+      .entries
+      .iter()
+      .filter(|e| q.level.map(|l| e.level >= l).unwrap_or(true))
+      .filter(|e| keyword.as_ref().map(|k| e.message.to_lowercase().contains(k)).unwrap_or(true))
+      .collect();
+    let total = matched.len();
+    let offset = q.offset.unwrap_or(0).min(total);
+    let limit = q.limit.unwrap_or(200);
+    let end = total - offset;
+    let start = end.saturating_sub(limit);
+    LogPage { entries: matched[start..end].iter().map(|e| (*e).clone()).collect(), total }
+  }
+
+  /// 下一条日志的序号，可用于标记某个时间点
+  pub fn next_seq(&self) -> u64 {
+    self.inner.lock().unwrap_or_else(|e| e.into_inner()).next_seq
+  }
+
+  /// 取序号 since 之后的最后 n 行原始消息，优先取 stderr，无 stderr 时取全部输出
+  pub fn tail_since(&self, since: u64, n: usize) -> Vec<String> {
+    let ring = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+    let recent: Vec<&LogEntry> = ring.entries.iter().filter(|e| e.seq >= since).collect();
+    let stderr: Vec<&LogEntry> = recent.iter().copied().filter(|e| e.stream == LogStream::Stderr).collect();
+    let picked = if stderr.is_empty() { recent } else { stderr };
+    let start = picked.len().saturating_sub(n);
+    picked[start..].iter().map(|e| e.message.clone()).collect()
+  }
+
+  pub fn clear(&self) {
+    let mut ring = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+    ring.entries.clear();
+  }
+}
+
+/// 启动读取线程：逐行写入缓冲区与日志文件、转发到 log 并推送 core_log 事件
+pub fn spawn_reader<R: Read + Send + 'static>(
+  source: R,
+  stream: LogStream,
+  buffer: Arc<LogBuffer>,
+  files: Arc<LogFileWriter>,
+  app: Option<AppHandle>,
+  instance: String,
+) {
+  std::thread::spawn(move || {
+    let reader = BufReader::new(source);
+    for line in reader.lines() {
+      match line {
+        Ok(l) => {
+          match stream {
+            LogStream::Stdout => log::info!(target: "mihomo-core", "[stdout] {}", l),
+            LogStream::Stderr => log::warn!(target: "mihomo-core", "[stderr] {}", l),
+          }
+          let entry = buffer.push(stream, &l);
+          files.append(&entry, &l);
+          if let Some(app) = app.as_ref() {
+            let _ = app.emit(CORE_LOG_EVENT, CoreLogEvent { instance: instance.clone(), entry });
+          }
+        }
+        Err(e) => {
+          log::error!(target: "mihomo-core", "读取 {} 失败: {}", stream.as_str(), e);
+          break;
+        }
+      }
+    }
+  });
+}
+
+/// 解析 mihomo 的 logfmt 输出：time="..." level=info msg="..."
+fn parse_line(line: &str, stream: LogStream) -> (LogLevel, String) {
+  let level = logfmt_value(line, "level").and_then(|v| LogLevel::parse(&v));
+  let message = logfmt_value(line, "msg").unwrap_or_else(|| line.to_string());
+  let fallback = match stream {
+    LogStream::Stdout => LogLevel::Info,
+    LogStream::Stderr => LogLevel::Warning,
+  };
+  (level.unwrap_or(fallback), message)
+}
+
+pub(crate) fn logfmt_value(line: &str, key: &str) -> Option<String> {
+  let pat = format!("{}=", key);
+  let mut search = line;
+  let rest = loop {
+    let idx = search.find(&pat)?;
+    // 需位于行首或空格之后，避免匹配到 msg 内容中的同名片段
+    let before_ok = idx == 0 || search[..idx].ends_with(' ');
+    if before_ok {
+      break &search[idx + pat.len()..];
+    }
+    search = &search[idx + pat.len()..];
+  };
+  if let Some(quoted) = rest.strip_prefix('"') {
+    let mut out = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+      match c {
+        '\\' => {
+          if let Some(n) = chars.next() {
+            out.push(n);
+          }
+        }
+        '"' => return Some(out),
+        _ => out.push(c),
+      }
+    }
+    Some(out)
+  } else {
+    Some(rest.split_whitespace().next().unwrap_or("").to_string())
+  }
+}
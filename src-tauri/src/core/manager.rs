@@ -1,9 +1,46 @@
-use std::path::PathBuf;
-use std::process::{Child, Command, Stdio};
-use std::io::{BufRead, BufReader};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::controller::Controller;
+use super::logfile::LogFileWriter;
+use super::logs::{self, LogBuffer, LogStream};
+use super::net::{self, ProxyRoute};
+use super::resources::ResourceHistory;
+use super::ports::{self, PortConflict};
+use super::process;
+use super::registry::SharedCore;
+use super::version;
+use super::util::unix_millis;
+
+/// 内核状态变化事件名
+pub const CORE_STATUS_EVENT: &str = "core_status_changed";
+
+/// 停止内核时等待其自行退出的默认时长，超时后强制结束
+pub const DEFAULT_STOP_GRACE_MS: u64 = 5000;
+
+/// 启动后等待 external-controller 就绪的默认时长
+pub const DEFAULT_READY_TIMEOUT_MS: u64 = 10_000;
+
+/// 等待就绪期间实例被停止或重新启动时返回的错误
+const START_CANCELLED: &str = "启动已被取消";
+
+/// 热重载请求期间实例被停止或重新启动时返回的错误
+const RELOAD_INTERRUPTED: &str = "热重载期间实例已被停止或重新启动";
+
+/// 就绪探测失败时附带的内核输出行数
+const READY_TAIL_LINES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum CoreStatus {
+  /// 进程已启动，正在等待就绪
+  Starting,
   Running,
   Stopped,
   Error,
@@ -15,12 +52,231 @@ impl Default for CoreStatus {
   }
 }
 
+impl CoreStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      CoreStatus::Starting => "starting",
+      CoreStatus::Running => "running",
+      CoreStatus::Stopped => "stopped",
+      CoreStatus::Error => "error",
+    }
+  }
+}
+
+/// 内核意外退出后的重启策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+  Never,
+  OnFailure,
+  Always,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestartConfig {
+  pub policy: RestartPolicy,
+  /// 首次重启前的等待时间，之后每次翻倍
+  pub initial_backoff_ms: u64,
+  pub max_backoff_ms: u64,
+  /// 在 window_secs 时间窗口内最多重启的次数，超过后放弃
+  pub max_restarts: u32,
+  pub window_secs: u64,
+}
+
+impl Default for RestartConfig {
+  fn default() -> Self {
+    Self {
+      policy: RestartPolicy::OnFailure,
+      initial_backoff_ms: 1000,
+      max_backoff_ms: 30_000,
+      max_restarts: 5,
+      window_secs: 60,
+    }
+  }
+}
+
+/// 内核进程的退出信息（Unix 上被信号终止时 code 为空）
+#[derive(Debug, Clone, Serialize)]
+pub struct ExitInfo {
+  pub code: Option<i32>,
+  pub signal: Option<i32>,
+  pub at: u64,
+}
+
+impl ExitInfo {
+  fn from_status(status: &ExitStatus) -> Self {
+    #[cfg(unix)]
+    let signal = {
+      use std::os::unix::process::ExitStatusExt;
+      status.signal()
+    };
+    #[cfg(not(unix))]
+    let signal = None;
+    Self { code: status.code(), signal, at: unix_millis() }
+  }
+
+  pub fn is_failure(&self) -> bool {
+    self.code != Some(0)
+  }
+}
+
+/// 停止内核时实际采用的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StopMethod {
+  /// 内核未在运行或已自行退出
+  None,
+  /// 收到 SIGTERM 后在宽限期内自行退出
+  Graceful,
+  /// 超过宽限期（或平台不支持 SIGTERM）后强制结束
+  Killed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StopReport {
+  pub method: StopMethod,
+  pub elapsed_ms: u64,
+  pub exit: Option<ExitInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreStatusEvent {
+  /// 实例 id
+  pub instance: String,
+  pub status: CoreStatus,
+  pub pid: Option<u32>,
+  pub last_exit: Option<ExitInfo>,
+  pub restart_count: u32,
+  pub next_restart_in_ms: Option<u64>,
+  pub message: Option<String>,
+}
+
+/// 重载配置时实际采用的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReloadMethod {
+  /// 通过 external-controller 的 PUT /configs 热重载
+  Hot,
+  /// 变更需要重启进程（或热重载不可用）时完整重启
+  Restart,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadReport {
+  pub method: ReloadMethod,
+  /// 走完整重启的原因
+  pub reason: Option<String>,
+  pub elapsed_ms: u64,
+}
+
+/// 变更后无法热重载、必须重启进程的配置项
+const RESTART_REQUIRED_KEYS: &[&[&str]] = &[
+  &["external-controller"],
+  &["external-controller-tls"],
+  &["external-controller-unix"],
+  &["secret"],
+  &["tun", "stack"],
+  &["tun", "device"],
+];
+
+/// 派生内核进程后等待就绪所需的信息，由 start、restart 等返回，调用方释放实例锁后交给 wait_ready
+#[derive(Debug)]
+#[must_use]
+pub struct ReadyProbe {
+  /// 派生时实例的启动代数；实例被停止或重新启动后不再一致
+  generation: u64,
+  config: PathBuf,
+  controller: Option<Controller>,
+  timeout: Duration,
+  /// 派生前的日志序号，用于附带启动输出
+  since: u64,
+  logs: Arc<LogBuffer>,
+  /// 由自动重启发起，失败时继续按重启策略安排
+  auto_restart: bool,
+}
+
+/// reload 的方式，由 plan_reload 在实例锁内决定；热重载的 HTTP 请求在释放锁后进行
+#[derive(Debug)]
+pub enum ReloadPlan {
+  Hot {
+    controller: Controller,
+    payload: String,
+    yaml: serde_yaml::Value,
+    /// 决定时实例的启动代数，提交前据此确认实例未被停止或重新启动
+    generation: u64,
+  },
+  Restart { reason: String, generation: u64 },
+}
+
+/// 以新配置完整重启的结果，需在释放实例锁后等待就绪
+#[derive(Debug)]
+pub struct RestartStep {
+  /// 以新配置派生的结果
+  pub started: Result<ReadyProbe, String>,
+  /// 上一次成功运行的配置，新配置无法启动时以它恢复
+  pub last_good: Option<LaunchSpec>,
+}
+
+enum ProbeCheck {
+  Alive,
+  Exited(String),
+  /// 实例已被停止或重新启动
+  Stale,
+}
+
+/// 一次内核启动所需的参数
+#[derive(Debug, Clone)]
+pub struct LaunchSpec {
+  /// 传给内核的配置文件（可能是合并了运行时覆盖项的生成文件）
+  pub config: PathBuf,
+  /// 用户选择的原始 profile 配置
+  pub source: PathBuf,
+  /// 内核工作目录，通过 -d 传入
+  pub work_dir: Option<PathBuf>,
+  /// profile 指定的已安装内核版本，None 时使用实例的 core_path
+  pub core_version: Option<String>,
+}
+
 #[derive(Debug, Default)]
 pub struct CoreManager {
+  /// 所属实例 id，见 CoreRegistry
+  pub instance: String,
   pub status: CoreStatus,
+  /// 实际传给内核的配置文件
   pub current_config: Option<PathBuf>,
+  /// 当前运行的原始 profile 配置
+  pub source_config: Option<PathBuf>,
+  /// 当前 profile 的工作目录，通过 -d 传给内核
+  pub work_dir: Option<PathBuf>,
+  /// 当前内核已加载的配置内容，用于判断重载能否热更新
+  running_yaml: Option<serde_yaml::Value>,
   pub core_path: Option<PathBuf>,
+  /// 当前运行所用的内核版本（来自 profile 设置），None 表示 core_path
+  pub core_version: Option<String>,
+  /// 已安装版本所在目录，用于解析 core_version
+  pub cores_dir: Option<PathBuf>,
+  /// 最近一次启动实际执行的内核文件（已解析符号链接）
+  pub core_binary: Option<PathBuf>,
   pub child: Option<Child>,
+  pub restart_config: RestartConfig,
+  /// 停止宽限期，未设置时使用 DEFAULT_STOP_GRACE_MS
+  pub stop_grace_ms: Option<u64>,
+  /// 启动就绪探测的超时，未设置时使用 DEFAULT_READY_TIMEOUT_MS
+  pub ready_timeout_ms: Option<u64>,
+  pub last_exit: Option<ExitInfo>,
+  /// 记录运行中内核 pid 的文件，用于下次启动时清理遗留进程
+  pub pid_file: Option<PathBuf>,
+  /// 内核输出的内存环形缓冲
+  pub logs: Arc<LogBuffer>,
+  /// 内核输出的持久化日志文件
+  pub log_files: Arc<LogFileWriter>,
+  /// 内核进程的资源占用采样
+  pub resources: Arc<ResourceHistory>,
+  restart_history: VecDeque<Instant>,
+  pending_restart: Option<Instant>,
+  /// 每次派生或停止进程时递增，用于识别过期的就绪探测
+  generation: u64,
+  app_handle: Option<AppHandle>,
 }
 
 impl CoreManager {
@@ -28,12 +284,102 @@ impl CoreManager {
     self.core_path = Some(path);
   }
 
-  pub fn start(&mut self, config_path: PathBuf) -> Result<(), String> {
+  /// 绑定应用句柄，用于向前端推送状态事件
+  pub fn attach_app_handle(&mut self, app: AppHandle) {
+    self.app_handle = Some(app);
+  }
+
+  pub fn set_pid_file(&mut self, path: PathBuf) {
+    self.pid_file = Some(path);
+  }
+
+  /// 清理上次会话（崩溃或被强制退出）遗留的内核进程；本实例已有子进程时 pid 文件属于它，不做处理
+  pub async fn reap_stale_core(&mut self) -> Option<u32> {
+    if self.child.is_some() {
+      return None;
+    }
+    process::reap_stale(self.pid_file.as_deref()?).await
+  }
+
+  /// 启动前检查配置中各监听端口是否已被其他进程占用
+  pub fn check_ports(&self, config_path: &Path) -> Result<Vec<PortConflict>, String> {
+    ports::check_config(config_path).map_err(|e| format!("读取配置失败: {}", e))
+  }
+
+  /// 解析要运行的内核：指定版本时使用 cores/<version> 下的可执行文件，否则为 core_path
+  pub fn resolve_core(&self, version: Option<&str>) -> Result<PathBuf, String> {
+    match version {
+      Some(v) => self
+        .cores_dir
+        .as_deref()
+        .and_then(|dir| version::installed_binary(dir, v))
+        .ok_or_else(|| format!("内核版本 {} 未安装", v)),
+      None => self.core_path.clone().ok_or_else(|| "尚未设置内核可执行文件路径".to_string()),
+    }
+  }
+
+  /// 当前运行的内核版本名：优先取启动时实际使用的版本目录，其次为 profile 指定的版本或 cores/current 指向的版本
+  pub fn running_version(&self) -> Option<String> {
+    if let (Some(bin), Some(dir)) = (self.core_binary.as_deref(), self.cores_dir.as_deref()) {
+      if let Some(v) = version::version_of_binary(dir, bin) {
+        return Some(v);
+      }
+    }
+    self
+      .core_version
+      .clone()
+      .or_else(|| self.cores_dir.as_deref().and_then(version::current_link_version))
+  }
+
+  /// 运行中内核提供给本机的代理入口，未运行或配置中没有代理端口时为 None
+  pub fn proxy_route(&self) -> Option<ProxyRoute> {
+    if self.status != CoreStatus::Running {
+      return None;
+    }
+    self.running_yaml.as_ref().and_then(net::core_proxy_route)
+  }
+
+  pub fn set_restart_config(&mut self, config: RestartConfig) {
+    self.restart_config = config;
+  }
+
+  /// 派生内核进程并返回就绪探测；调用方应释放实例锁后以 wait_ready 等待
+  pub fn start(&mut self, spec: LaunchSpec) -> Result<ReadyProbe, String> {
+    // 手动启动时清空重启计数
+    self.restart_history.clear();
+    self.pending_restart = None;
+    self.source_config = Some(spec.source);
+    self.work_dir = spec.work_dir;
+    self.core_version = spec.core_version;
+    self.launch(spec.config)
+  }
+
+  /// 派生内核进程并进入 Starting，就绪后由 wait_ready 报告 Running
+  fn launch(&mut self, config_path: PathBuf) -> Result<ReadyProbe, String> {
+    let since = self.logs.next_seq();
+    match self.spawn_core(config_path.clone()) {
+      Ok(()) => {
+        self.set_status(CoreStatus::Starting, None);
+        Ok(ReadyProbe {
+          generation: self.generation,
+          controller: Controller::from_config(&config_path),
+          config: config_path,
+          timeout: Duration::from_millis(self.ready_timeout_ms.unwrap_or(DEFAULT_READY_TIMEOUT_MS)),
+          since,
+          logs: self.logs.clone(),
+          auto_restart: false,
+        })
+      }
+      Err(msg) => {
+        self.set_status(CoreStatus::Error, Some(msg.clone()));
+        Err(msg)
+      }
+    }
+  }
+
+  fn spawn_core(&mut self, config_path: PathBuf) -> Result<(), String> {
     self.current_config = Some(config_path.clone());
-    let core = self
-      .core_path
-      .as_ref()
-      .ok_or_else(|| "尚未设置内核可执行文件路径".to_string())?;
+    let core = self.resolve_core(self.core_version.as_deref())?;
 
     if !core.exists() {
       return Err("内核可执行文件不存在".into());
@@ -42,83 +388,460 @@ impl CoreManager {
       return Err("配置文件不存在".into());
     }
 
-    // 以常见方式启动：mihomo -f <config>
-    let mut cmd = Command::new(core);
+    // 以常见方式启动：mihomo [-d <work_dir>] -f <config>
+    let mut cmd = Command::new(&core);
+    if let Some(dir) = self.work_dir.as_ref() {
+      std::fs::create_dir_all(dir).map_err(|e| format!("创建工作目录失败: {}", e))?;
+      cmd.arg("-d").arg(dir);
+    }
     cmd.arg("-f").arg(&config_path);
     cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    process::isolate_process_group(&mut cmd);
 
-    match cmd.spawn() {
-      Ok(mut child) => {
-        // 将子进程的 stdout/stderr 转发到日志，便于在 dev 终端观察运行日志
-        if let Some(stdout) = child.stdout.take() {
-          std::thread::spawn(move || {
-            let reader = BufReader::new(stdout);
-            for line in reader.lines() {
-              match line {
-                Ok(l) => log::info!(target: "mihomo-core", "[stdout] {}", l),
-                Err(e) => {
-                  log::error!(target: "mihomo-core", "读取 stdout 失败: {}", e);
-                  break;
-                }
-              }
-            }
-          });
-        }
-        if let Some(stderr) = child.stderr.take() {
-          std::thread::spawn(move || {
-            let reader = BufReader::new(stderr);
-            for line in reader.lines() {
-              match line {
-                Ok(l) => log::warn!(target: "mihomo-core", "[stderr] {}", l),
-                Err(e) => {
-                  log::error!(target: "mihomo-core", "读取 stderr 失败: {}", e);
-                  break;
-                }
-              }
-            }
-          });
-        }
-        self.child = Some(child);
-        self.status = CoreStatus::Running;
+    let mut child = cmd.spawn().map_err(|e| format!("启动失败: {}", e))?;
+    self.generation += 1;
+    // 记录解析符号链接后的实际文件，切换 current 后仍能判断运行中的版本
+    self.core_binary = std::fs::canonicalize(&core).ok();
+    if let Some(pid_file) = self.pid_file.as_ref() {
+      process::write_pid_file(pid_file, child.id());
+    }
+    // 将子进程的 stdout/stderr 写入日志缓冲区与日志文件，并推送给前端
+    if let Some(stdout) = child.stdout.take() {
+      logs::spawn_reader(stdout, LogStream::Stdout, self.logs.clone(), self.log_files.clone(), self.app_handle.clone(), self.instance.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+      logs::spawn_reader(stderr, LogStream::Stderr, self.logs.clone(), self.log_files.clone(), self.app_handle.clone(), self.instance.clone());
+    }
+    self.child = Some(child);
+    Ok(())
+  }
+
+  /// 检查探测对应的子进程是否仍在运行
+  fn check_probe(&mut self, probe: &ReadyProbe) -> ProbeCheck {
+    if self.generation != probe.generation {
+      return ProbeCheck::Stale;
+    }
+    let exited = match self.child.as_mut() {
+      Some(child) => child.try_wait().ok().flatten(),
+      None => return ProbeCheck::Stale,
+    };
+    match exited {
+      Some(status) => {
+        self.child = None;
+        self.clear_pid_file();
+        let exit = ExitInfo::from_status(&status);
+        let msg = format!("内核启动后立即退出 (code={:?} signal={:?})", exit.code, exit.signal);
+        self.last_exit = Some(exit);
+        ProbeCheck::Exited(msg)
+      }
+      None => ProbeCheck::Alive,
+    }
+  }
+
+  /// 记录就绪探测的结果；探测期间实例已被停止或重新启动时不改变状态
+  fn finish_probe(&mut self, probe: &ReadyProbe, outcome: Result<(), String>) -> Result<(), String> {
+    if self.generation != probe.generation {
+      return Err(START_CANCELLED.into());
+    }
+    match outcome {
+      Ok(()) => {
+        self.running_yaml = read_yaml(&probe.config);
+        self.set_status(CoreStatus::Running, None);
         Ok(())
       }
-      Err(e) => {
-        self.status = CoreStatus::Error;
-        Err(format!("启动失败: {}", e))
+      Err(msg) => {
+        self.discard_child();
+        self.set_status(CoreStatus::Error, Some(msg.clone()));
+        if probe.auto_restart {
+          self.on_core_exit(true);
+        }
+        Err(msg)
       }
     }
   }
 
-  pub fn stop(&mut self) -> Result<(), String> {
-    if let Some(child) = self.child.as_mut() {
-      if let Err(e) = child.kill() {
-        return Err(format!("停止进程失败: {}", e));
+  /// 直接结束未就绪的子进程，不改变状态
+  fn discard_child(&mut self) {
+    if let Some(mut child) = self.child.take() {
+      if !process::kill_group(child.id()) {
+        let _ = child.kill();
       }
       let _ = child.wait();
     }
-    self.child = None;
-    self.status = CoreStatus::Stopped;
-    Ok(())
+    self.clear_pid_file();
   }
 
-  pub fn get_status(&mut self) -> CoreStatus {
+  pub async fn stop(&mut self) -> Result<StopReport, String> {
+    let grace = Duration::from_millis(self.stop_grace_ms.unwrap_or(DEFAULT_STOP_GRACE_MS));
+    self.stop_with_grace(grace).await
+  }
+
+  /// 先发送 SIGTERM 让内核清理 TUN/路由/缓存，超过宽限期仍未退出再强制结束
+  pub async fn stop_with_grace(&mut self, grace: Duration) -> Result<StopReport, String> {
+    // 用户主动停止时取消待执行的自动重启与进行中的就绪探测
+    self.pending_restart = None;
+    self.generation += 1;
+    let started = Instant::now();
+    let mut report = StopReport { method: StopMethod::None, elapsed_ms: 0, exit: None };
     if let Some(child) = self.child.as_mut() {
-      match child.try_wait() {
-        Ok(Some(_)) => CoreStatus::Stopped,
-        Ok(None) => CoreStatus::Running,
-        Err(_) => CoreStatus::Error,
+      let mut exited = child.try_wait().map_err(|e| format!("检查进程状态失败: {}", e))?;
+      if exited.is_none() && process::terminate_group(child.id()) {
+        while started.elapsed() < grace {
+          exited = child.try_wait().map_err(|e| format!("检查进程状态失败: {}", e))?;
+          if exited.is_some() {
+            report.method = StopMethod::Graceful;
+            break;
+          }
+          tokio::time::sleep(Duration::from_millis(50)).await;
+        }
       }
-    } else {
-      self.status
+      if exited.is_none() {
+        log::warn!(target: "mihomo-core", "内核未在 {} 毫秒内退出，强制结束", grace.as_millis());
+        if !process::kill_group(child.id()) {
+          if let Err(e) = child.kill() {
+            return Err(format!("停止进程失败: {}", e));
+          }
+        }
+        report.method = StopMethod::Killed;
+        exited = child.wait().ok();
+      }
+      report.exit = exited.as_ref().map(ExitInfo::from_status);
+    }
+    report.elapsed_ms = started.elapsed().as_millis() as u64;
+    if report.exit.is_some() {
+      self.last_exit = report.exit.clone();
     }
+    self.child = None;
+    self.clear_pid_file();
+    self.set_status(CoreStatus::Stopped, None);
+    Ok(report)
   }
 
-  pub fn restart(&mut self) -> Result<(), String> {
+  pub fn get_status(&mut self) -> CoreStatus {
+    self.check_exit();
+    self.status
+  }
+
+  /// 停止后以当前配置重新派生内核，返回就绪探测
+  pub async fn restart(&mut self) -> Result<ReadyProbe, String> {
     let cfg = self
       .current_config
       .clone()
       .ok_or_else(|| "尚未指定配置文件".to_string())?;
-    self.stop().ok();
-    self.start(cfg)
+    self.stop().await.ok();
+    let spec = LaunchSpec {
+      source: self.source_config.clone().unwrap_or_else(|| cfg.clone()),
+      config: cfg,
+      work_dir: self.work_dir.clone(),
+      core_version: self.core_version.clone(),
+    };
+    self.start(spec)
+  }
+
+  /// 决定如何应用新配置：仅配置内容变化时经 external-controller 热重载，保留现有连接；
+  /// 工作目录、控制器地址或 TUN 栈等变化时需完整重启
+  pub fn plan_reload(&self, spec: &LaunchSpec) -> Result<ReloadPlan, String> {
+    if self.child.is_none() {
+      return Err("内核未运行".into());
+    }
+    let payload = std::fs::read_to_string(&spec.config).map_err(|e| format!("读取配置失败: {}", e))?;
+    let yaml: serde_yaml::Value = serde_yaml::from_str(&payload).map_err(|e| format!("YAML 解析失败: {}", e))?;
+
+    let reason = if spec.work_dir != self.work_dir {
+      Some("工作目录变化".to_string())
+    } else if spec.core_version != self.core_version {
+      Some("内核版本变化".to_string())
+    } else {
+      self.running_yaml.as_ref().and_then(|old| restart_reason(old, &yaml))
+    };
+    let controller = self.running_yaml.as_ref().and_then(Controller::from_yaml);
+    let generation = self.generation;
+    Ok(match (reason, controller) {
+      (Some(reason), _) => ReloadPlan::Restart { reason, generation },
+      (None, None) => ReloadPlan::Restart { reason: "未配置 external-controller，无法热重载".into(), generation },
+      (None, Some(controller)) => ReloadPlan::Hot { controller, payload, yaml, generation },
+    })
+  }
+
+  /// 实例仍在运行且未被重新启动
+  fn is_generation(&self, generation: u64) -> bool {
+    self.generation == generation && self.child.is_some()
+  }
+
+  /// 记录已热重载的配置；请求期间实例被停止或重新启动时报错
+  fn commit_hot_reload(&mut self, spec: LaunchSpec, yaml: serde_yaml::Value, generation: u64) -> Result<(), String> {
+    if !self.is_generation(generation) {
+      return Err(RELOAD_INTERRUPTED.into());
+    }
+    log::info!(target: "mihomo-core", "配置已热重载");
+    self.current_config = Some(spec.config);
+    self.source_config = Some(spec.source);
+    self.running_yaml = Some(yaml);
+    self.set_status(CoreStatus::Running, Some("配置已热重载".into()));
+    Ok(())
+  }
+
+  /// 保存当前配置作为恢复用的副本后以新配置重新派生内核
+  async fn restart_with(&mut self, spec: LaunchSpec, reason: &str) -> RestartStep {
+    log::info!(target: "mihomo-core", "配置需完整重启: {}", reason);
+    let last_good = self.save_last_good();
+    self.stop().await.ok();
+    let started = self.start(spec);
+    RestartStep { started, last_good }
+  }
+
+  /// 将当前已加载的配置另存为 <runtime>.last-good.yaml，返回以它启动的参数；运行时配置会被新配置覆盖，因此需要副本
+  fn save_last_good(&self) -> Option<LaunchSpec> {
+    let text = serde_yaml::to_string(self.running_yaml.as_ref()?).ok()?;
+    let current = self.current_config.as_ref()?;
+    // 已经以副本运行时原地覆盖，避免文件名层层叠加
+    let config = if current.to_string_lossy().ends_with(".last-good.yaml") { current.clone() } else { current.with_extension("last-good.yaml") };
+    if let Err(e) = std::fs::write(&config, text) {
+      log::warn!(target: "mihomo-core", "保存上一次有效的配置失败: {}", e);
+      return None;
+    }
+    Some(LaunchSpec {
+      config,
+      source: self.source_config.clone()?,
+      work_dir: self.work_dir.clone(),
+      core_version: self.core_version.clone(),
+    })
+  }
+
+  /// 检查子进程是否退出，并按重启策略安排重启；启动探测进行中时由探测负责
+  pub fn check_exit(&mut self) {
+    if self.status == CoreStatus::Starting {
+      return;
+    }
+    if let Some(child) = self.child.as_mut() {
+      match child.try_wait() {
+        Ok(Some(status)) => {
+          self.child = None;
+          self.clear_pid_file();
+          let exit = ExitInfo::from_status(&status);
+          log::warn!(target: "mihomo-core", "内核进程已退出: code={:?} signal={:?}", exit.code, exit.signal);
+          let failed = exit.is_failure();
+          self.last_exit = Some(exit);
+          self.on_core_exit(failed);
+        }
+        Ok(None) => {}
+        Err(e) => {
+          log::error!(target: "mihomo-core", "检查内核进程状态失败: {}", e);
+        }
+      }
+    }
+  }
+
+  /// 执行到期的自动重启，返回需在释放实例锁后等待的就绪探测
+  pub fn due_restart(&mut self) -> Option<ReadyProbe> {
+    let at = self.pending_restart?;
+    if Instant::now() < at {
+      return None;
+    }
+    self.pending_restart = None;
+    let cfg = self.current_config.clone()?;
+    log::info!(target: "mihomo-core", "自动重启内核（第 {} 次）", self.restart_history.len());
+    match self.launch(cfg) {
+      Ok(mut probe) => {
+        probe.auto_restart = true;
+        Some(probe)
+      }
+      Err(_) => {
+        self.on_core_exit(true);
+        None
+      }
+    }
+  }
+
+  fn on_core_exit(&mut self, failed: bool) {
+    let status = if failed { CoreStatus::Error } else { CoreStatus::Stopped };
+    let should_restart = match self.restart_config.policy {
+      RestartPolicy::Never => false,
+      RestartPolicy::OnFailure => failed,
+      RestartPolicy::Always => true,
+    };
+    if !should_restart || self.current_config.is_none() {
+      self.set_status(status, None);
+      return;
+    }
+
+    // 仅统计时间窗口内的重启次数
+    let now = Instant::now();
+    let window = Duration::from_secs(self.restart_config.window_secs);
+    while let Some(front) = self.restart_history.front() {
+      if now.duration_since(*front) > window {
+        self.restart_history.pop_front();
+      } else {
+        break;
+      }
+    }
+    if self.restart_history.len() as u32 >= self.restart_config.max_restarts {
+      let msg = format!(
+        "{} 秒内已重启 {} 次，放弃自动重启",
+        self.restart_config.window_secs,
+        self.restart_history.len()
+      );
+      log::error!(target: "mihomo-core", "{}", msg);
+      self.set_status(CoreStatus::Error, Some(msg));
+      return;
+    }
+
+    let backoff = self.backoff_for(self.restart_history.len() as u32);
+    self.restart_history.push_back(now);
+    self.pending_restart = Some(now + backoff);
+    self.set_status(status, Some(format!("{} 毫秒后自动重启", backoff.as_millis())));
+  }
+
+  fn backoff_for(&self, attempt: u32) -> Duration {
+    let cfg = &self.restart_config;
+    let ms = cfg.initial_backoff_ms.saturating_mul(1u64 << attempt.min(16)).min(cfg.max_backoff_ms);
+    Duration::from_millis(ms)
+  }
+
+  pub fn status_event(&self, message: Option<String>) -> CoreStatusEvent {
+    CoreStatusEvent {
+      instance: self.instance.clone(),
+      status: self.status,
+      pid: self.child.as_ref().map(|c| c.id()),
+      last_exit: self.last_exit.clone(),
+      restart_count: self.restart_history.len() as u32,
+      next_restart_in_ms: self
+        .pending_restart
+        .map(|at| at.saturating_duration_since(Instant::now()).as_millis() as u64),
+      message,
+    }
+  }
+
+  fn clear_pid_file(&self) {
+    if let Some(pid_file) = self.pid_file.as_ref() {
+      process::remove_pid_file(pid_file);
+    }
+  }
+
+  fn set_status(&mut self, status: CoreStatus, message: Option<String>) {
+    self.status = status;
+    if let Some(app) = self.app_handle.as_ref() {
+      let _ = app.emit(CORE_STATUS_EVENT, self.status_event(message));
+    }
+  }
+}
+
+/// 等待内核就绪：轮询 external-controller 的 /version，直到应答、进程退出或超时。
+/// 只在检查子进程与记录结果时短暂持有实例锁，期间停止、状态查询等命令不受阻塞
+pub async fn wait_ready(core: &SharedCore, probe: ReadyProbe) -> Result<(), String> {
+  let started = Instant::now();
+  let outcome = loop {
+    match core.lock().await.check_probe(&probe) {
+      ProbeCheck::Alive => {}
+      ProbeCheck::Exited(msg) => break Err(msg),
+      ProbeCheck::Stale => return Err(START_CANCELLED.into()),
+    }
+
+    match probe.controller.as_ref() {
+      Some(c) => {
+        if let Ok(version) = c.version().await {
+          log::info!(target: "mihomo-core", "内核已就绪，版本 {}", version);
+          break Ok(());
+        }
+      }
+      // 未配置 external-controller 时无法探测，进程存活一小段时间即视为就绪
+      None => {
+        if started.elapsed() >= Duration::from_millis(1000) {
+          break Ok(());
+        }
+      }
+    }
+
+    if started.elapsed() >= probe.timeout {
+      break Err(format!("等待内核就绪超时（{} 毫秒）", probe.timeout.as_millis()));
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+  };
+  let outcome = match outcome {
+    Ok(()) => Ok(()),
+    Err(msg) => Err(with_output_tail(&probe.logs, msg, probe.since).await),
+  };
+  core.lock().await.finish_probe(&probe, outcome)
+}
+
+/// 以新配置启动实例并等待就绪
+pub async fn start(core: &SharedCore, spec: LaunchSpec) -> Result<(), String> {
+  let probe = core.lock().await.start(spec)?;
+  wait_ready(core, probe).await
+}
+
+/// 重启实例并等待就绪
+pub async fn restart(core: &SharedCore) -> Result<(), String> {
+  let probe = core.lock().await.restart().await?;
+  wait_ready(core, probe).await
+}
+
+/// 应用新配置，需要完整重启时等待新进程就绪；新配置无法启动时以上一次有效的配置重新启动。
+/// 热重载请求（PUT /configs 可能因刷新 provider 而较慢）与等待就绪期间不持有实例锁
+pub async fn reload(core: &SharedCore, spec: LaunchSpec) -> Result<ReloadReport, String> {
+  let begun = Instant::now();
+  let plan = core.lock().await.plan_reload(&spec)?;
+  let (reason, generation) = match plan {
+    ReloadPlan::Restart { reason, generation } => (reason, generation),
+    ReloadPlan::Hot { controller, payload, yaml, generation } => match controller.reload_payload(&payload).await {
+      Ok(()) => {
+        core.lock().await.commit_hot_reload(spec, yaml, generation)?;
+        return Ok(ReloadReport { method: ReloadMethod::Hot, reason: None, elapsed_ms: begun.elapsed().as_millis() as u64 });
+      }
+      // 控制器仍可访问说明是配置被内核拒绝，保留旧配置并报错；否则回退为重启
+      Err(e) if controller.version().await.is_ok() => return Err(e.to_string()),
+      Err(e) => (format!("热重载不可用: {}", e), generation),
+    },
+  };
+  let RestartStep { started, last_good } = {
+    let mut mgr = core.lock().await;
+    if !mgr.is_generation(generation) {
+      return Err(RELOAD_INTERRUPTED.into());
+    }
+    mgr.restart_with(spec, &reason).await
+  };
+  let result = match started {
+    Ok(probe) => wait_ready(core, probe).await,
+    Err(e) => Err(e),
+  };
+  match (result, last_good) {
+    (Ok(()), _) => Ok(ReloadReport { method: ReloadMethod::Restart, reason: Some(reason), elapsed_ms: begun.elapsed().as_millis() as u64 }),
+    // 等待期间被用户停止时不再恢复
+    (Err(e), _) if e == START_CANCELLED => Err(e),
+    (Err(e), None) => Err(e),
+    (Err(e), Some(previous)) => {
+      log::warn!(target: "mihomo-core", "新配置启动失败，恢复上一次有效的配置: {}", e);
+      match start(core, previous).await {
+        Ok(()) => Err(format!("新配置启动失败，已恢复上一次有效的配置：{}", e)),
+        Err(e2) => Err(format!("新配置启动失败：{}；恢复上一次有效的配置也失败：{}", e, e2)),
+      }
+    }
+  }
+}
+
+async fn with_output_tail(logs: &LogBuffer, msg: String, since: u64) -> String {
+  // 等待读取线程消费完最后的输出
+  tokio::time::sleep(Duration::from_millis(100)).await;
+  let tail = logs.tail_since(since, READY_TAIL_LINES);
+  if tail.is_empty() {
+    msg
+  } else {
+    format!("{}：\n{}", msg, tail.join("\n"))
+  }
+}
+
+fn read_yaml(path: &Path) -> Option<serde_yaml::Value> {
+  let text = std::fs::read_to_string(path).ok()?;
+  serde_yaml::from_str(&text).ok()
+}
+
+/// 比较需要重启的配置项，返回第一个发生变化的项
+fn restart_reason(old: &serde_yaml::Value, new: &serde_yaml::Value) -> Option<String> {
+  fn lookup<'a>(v: &'a serde_yaml::Value, path: &[&str]) -> Option<&'a serde_yaml::Value> {
+    path.iter().try_fold(v, |cur, key| cur.get(*key))
   }
+  RESTART_REQUIRED_KEYS
+    .iter()
+    .find(|path| lookup(old, path) != lookup(new, path))
+    .map(|path| format!("{} 已变化", path.join(".")))
 }
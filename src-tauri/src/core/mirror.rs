@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::util::unix_millis;
+
+/// 获取发布信息与下载资产的途径，按设置中的顺序依次尝试
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Mirror {
+  /// 直连 core.api_base 与资产原始地址
+  Direct,
+  /// URL 前缀代理：请求 <prefix><原始 URL>，如 https://ghproxy.example/https://api.github.com/...
+  Prefix { prefix: String },
+  /// 兼容 GitHub releases API 的服务（Gitea、GitHub Enterprise 等），资产地址使用其返回值
+  ApiBase { base: String },
+  /// 静态 JSON 索引：GitHub release 对象的数组，按发布时间倒序
+  Index { url: String },
+}
+
+impl Mirror {
+  /// 健康记录的键
+  pub fn label(&self) -> String {
+    match self {
+      Mirror::Direct => "direct".into(),
+      Mirror::Prefix { prefix } => format!("prefix:{}", prefix),
+      Mirror::ApiBase { base } => format!("api:{}", base),
+      Mirror::Index { url } => format!("index:{}", url),
+    }
+  }
+
+  /// 该途径下的 API 地址；静态索引没有 API
+  pub fn api_base(&self, default_base: &str) -> Option<String> {
+    match self {
+      Mirror::Direct => Some(default_base.to_string()),
+      Mirror::Prefix { prefix } => Some(format!("{}{}", prefix, default_base)),
+      Mirror::ApiBase { base } => Some(base.clone()),
+      Mirror::Index { .. } => None,
+    }
+  }
+
+  /// 改写资产地址：前缀代理在原始地址前加前缀，其他途径保持不变
+  pub fn rewrite(&self, url: &str) -> String {
+    match self {
+      Mirror::Prefix { prefix } if !url.starts_with(prefix.as_str()) => format!("{}{}", prefix, url),
+      _ => url.to_string(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MirrorHealth {
+  pub successes: u32,
+  pub failures: u32,
+  /// 连续失败次数，成功后清零
+  pub consecutive_failures: u32,
+  /// 最近一次成功请求的耗时
+  pub last_latency_ms: Option<u64>,
+  pub last_error: Option<String>,
+  /// 毫秒级 Unix 时间戳
+  pub last_success_at: Option<u64>,
+  pub last_failure_at: Option<u64>,
+}
+
+/// 镜像及其健康状况
+#[derive(Debug, Clone, Serialize)]
+pub struct MirrorStatus {
+  pub mirror: Mirror,
+  pub health: MirrorHealth,
+}
+
+/// 各镜像的请求结果统计，仅保存在内存中
+#[derive(Debug, Default)]
+pub struct MirrorHealthTable {
+  inner: Mutex<HashMap<String, MirrorHealth>>,
+}
+
+impl MirrorHealthTable {
+  pub fn record_success(&self, mirror: &Mirror, latency: Duration) {
+    let mut table = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+    let h = table.entry(mirror.label()).or_default();
+    h.successes += 1;
+    h.consecutive_failures = 0;
+    h.last_latency_ms = Some(latency.as_millis() as u64);
+    h.last_success_at = Some(unix_millis());
+  }
+
+  pub fn record_failure(&self, mirror: &Mirror, error: &str) {
+    let mut table = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+    let h = table.entry(mirror.label()).or_default();
+    h.failures += 1;
+    h.consecutive_failures += 1;
+    h.last_error = Some(error.to_string());
+    h.last_failure_at = Some(unix_millis());
+  }
+
+  pub fn status(&self, mirrors: &[Mirror]) -> Vec<MirrorStatus> {
+    let table = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+    mirrors
+      .iter()
+      .map(|m| MirrorStatus { mirror: m.clone(), health: table.get(&m.label()).cloned().unwrap_or_default() })
+      .collect()
+  }
+}
@@ -1,2 +1,16 @@
+pub mod controller;
+pub mod download;
+pub mod logfile;
+pub mod logs;
 pub mod manager;
+pub mod mirror;
+pub mod net;
+pub mod ports;
+pub mod process;
+pub mod registry;
+pub mod resources;
+pub mod supervisor;
+#[cfg(test)]
+mod test_server;
+pub mod util;
 pub mod version;
@@ -0,0 +1,154 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use super::ports::join_host_port;
+use super::registry::{CoreRegistry, DEFAULT_INSTANCE};
+use crate::config::settings::SettingsStore;
+
+type Shared<T> = tauri::async_runtime::Mutex<T>;
+
+/// 出站请求（发布信息、版本号、校验文件与资产下载）使用的代理
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ProxySetting {
+  /// 直连，忽略代理环境变量
+  None,
+  /// 使用 HTTP_PROXY、HTTPS_PROXY、ALL_PROXY 与 NO_PROXY 环境变量
+  #[default]
+  System,
+  /// 指定的代理地址，支持 http://、https://、socks5:// 与 socks5h://，可带 user:pass@
+  Custom { url: String },
+  /// 经由运行中内核实例的 mixed-port（其次为 port、socks-port），未指定实例时为默认实例；实例未运行时直连
+  ViaCore { instance: Option<String> },
+}
+
+/// 解析后的代理路线
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyRoute {
+  Direct,
+  System,
+  Url { url: String, auth: Option<(String, String)> },
+}
+
+impl ProxyRoute {
+  /// 用于日志，不含认证信息
+  pub fn describe(&self) -> String {
+    match self {
+      ProxyRoute::Direct => "direct".into(),
+      ProxyRoute::System => "system".into(),
+      ProxyRoute::Url { url, .. } => match reqwest::Url::parse(url) {
+        Ok(mut u) => {
+          let _ = u.set_username("");
+          let _ = u.set_password(None);
+          u.to_string()
+        }
+        Err(_) => "invalid proxy url".into(),
+      },
+    }
+  }
+}
+
+/// 按路线缓存的共享客户端，路线不变时复用连接池
+#[derive(Debug, Default)]
+pub struct HttpClients {
+  cached: Option<(ProxyRoute, reqwest::Client)>,
+}
+
+impl HttpClients {
+  pub fn get(&mut self, route: ProxyRoute) -> Result<reqwest::Client> {
+    if let Some((cached, client)) = self.cached.as_ref() {
+      if *cached == route {
+        return Ok(client.clone());
+      }
+    }
+    log::info!("building http client via {}", route.describe());
+    let client = build_client(&route)?;
+    self.cached = Some((route, client.clone()));
+    Ok(client)
+  }
+}
+
+pub fn build_client(route: &ProxyRoute) -> Result<reqwest::Client> {
+  let mut headers = reqwest::header::HeaderMap::new();
+  headers.insert(
+    reqwest::header::ACCEPT,
+    reqwest::header::HeaderValue::from_static("application/vnd.github+json"),
+  );
+  headers.insert(
+    reqwest::header::USER_AGENT,
+    reqwest::header::HeaderValue::from_static("mihomo-gui/0.1"),
+  );
+
+  let builder = reqwest::Client::builder().default_headers(headers);
+  let builder = match route {
+    ProxyRoute::Direct => builder.no_proxy(),
+    ProxyRoute::System => builder,
+    ProxyRoute::Url { url, auth } => {
+      let mut proxy = reqwest::Proxy::all(url.as_str()).with_context(|| format!("无效的代理地址: {}", route.describe()))?;
+      if let Some((user, pass)) = auth {
+        proxy = proxy.basic_auth(user, pass);
+      }
+      builder.proxy(proxy)
+    }
+  };
+  Ok(builder.build()?)
+}
+
+/// 内核配置中可供本机使用的代理入口：mixed-port 与 port 为 HTTP 代理，socks-port 为 SOCKS5；
+/// 配置了 authentication 时使用其第一项
+pub fn core_proxy_route(yaml: &serde_yaml::Value) -> Option<ProxyRoute> {
+  let port = |key: &str| yaml.get(key).and_then(|v| v.as_u64()).filter(|p| *p > 0 && *p <= u16::MAX as u64).map(|p| p as u16);
+  // 开启 allow-lan 且绑定了具体地址时内核不监听回环地址
+  let allow_lan = yaml.get("allow-lan").and_then(|v| v.as_bool()).unwrap_or(false);
+  let bind = yaml.get("bind-address").and_then(|v| v.as_str()).unwrap_or("*");
+  let host = if allow_lan && !matches!(bind, "" | "*" | "0.0.0.0" | "::") { bind } else { "127.0.0.1" };
+
+  let url = if let Some(p) = port("mixed-port").or_else(|| port("port")) {
+    format!("http://{}", join_host_port(host, p))
+  } else {
+    format!("socks5h://{}", join_host_port(host, port("socks-port")?))
+  };
+  let auth = yaml
+    .get("authentication")
+    .and_then(|v| v.as_sequence())
+    .and_then(|list| list.iter().filter_map(|v| v.as_str()).next())
+    .and_then(|s| s.split_once(':'))
+    .map(|(user, pass)| (user.to_string(), pass.to_string()));
+  Some(ProxyRoute::Url { url, auth })
+}
+
+/// 按代理设置解析路线；ViaCore 查询实例的运行状态与运行时配置
+pub async fn resolve_route(app: &AppHandle, setting: &ProxySetting) -> ProxyRoute {
+  match setting {
+    ProxySetting::None => ProxyRoute::Direct,
+    ProxySetting::System => ProxyRoute::System,
+    ProxySetting::Custom { url } => {
+      let url = url.trim();
+      if url.is_empty() {
+        ProxyRoute::Direct
+      } else {
+        ProxyRoute::Url { url: url.to_string(), auth: None }
+      }
+    }
+    ProxySetting::ViaCore { instance } => {
+      let core = app.state::<Shared<CoreRegistry>>().lock().await.get(instance.as_deref());
+      let route = match core {
+        Ok(core) => core.lock().await.proxy_route(),
+        Err(_) => None,
+      };
+      route.unwrap_or_else(|| {
+        log::warn!("core instance {} is not running or has no proxy port, connecting directly", instance.as_deref().unwrap_or(DEFAULT_INSTANCE));
+        ProxyRoute::Direct
+      })
+    }
+  }
+}
+
+/// 出站请求共用的客户端：按当前代理设置解析路线并复用缓存的客户端。
+/// 会依次获取设置、实例注册表与实例的锁，调用方不能持有这些锁
+pub async fn http_client(app: &AppHandle) -> Result<reqwest::Client, String> {
+  let setting = app.state::<Shared<SettingsStore>>().lock().await.settings.network.proxy.clone();
+  let route = resolve_route(app, &setting).await;
+  app.state::<Shared<HttpClients>>().lock().await.get(route).map_err(|e| format!("{:#}", e))
+}
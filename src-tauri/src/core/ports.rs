@@ -0,0 +1,239 @@
+use std::fs;
+use std::io;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs, UdpSocket};
+use std::path::Path;
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+  Tcp,
+  Udp,
+}
+
+/// 配置中声明的一个监听地址
+#[derive(Debug, Clone, Serialize)]
+pub struct ListenSpec {
+  /// 配置项名称，如 mixed-port、dns.listen
+  pub name: String,
+  pub address: String,
+  pub protocol: Protocol,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PortConflict {
+  pub name: String,
+  pub address: String,
+  pub protocol: Protocol,
+  /// 占用该端口的进程（仅 Linux 可从 /proc 获取）
+  pub pid: Option<u32>,
+  pub process: Option<String>,
+  pub message: String,
+}
+
+/// 从配置中收集内核将要监听的地址
+pub fn listen_specs(yaml: &serde_yaml::Value) -> Vec<ListenSpec> {
+  let mut specs = Vec::new();
+  let allow_lan = yaml.get("allow-lan").and_then(|v| v.as_bool()).unwrap_or(false);
+  let bind = yaml.get("bind-address").and_then(|v| v.as_str()).unwrap_or("*");
+  // allow-lan 关闭时内核只监听回环地址
+  let host = if !allow_lan {
+    "127.0.0.1"
+  } else if bind == "*" || bind.is_empty() {
+    "0.0.0.0"
+  } else {
+    bind
+  };
+
+  for key in ["mixed-port", "port", "socks-port", "redir-port", "tproxy-port"] {
+    let port = yaml.get(key).and_then(|v| v.as_u64()).filter(|p| *p > 0 && *p <= u16::MAX as u64);
+    if let Some(port) = port {
+      specs.push(ListenSpec { name: key.into(), address: join_host_port(host, port as u16), protocol: Protocol::Tcp });
+    }
+  }
+
+  if let Some(listen) = yaml.get("dns").and_then(|d| d.get("listen")).and_then(|v| v.as_str()) {
+    let enabled = yaml.get("dns").and_then(|d| d.get("enable")).and_then(|v| v.as_bool()).unwrap_or(false);
+    if enabled {
+      specs.push(ListenSpec { name: "dns.listen".into(), address: normalize_listen(listen), protocol: Protocol::Udp });
+    }
+  }
+
+  if let Some(ctrl) = yaml
+    .get("external-controller")
+    .or_else(|| yaml.get("external_controller"))
+    .and_then(|v| v.as_str())
+    .filter(|s| !s.trim().is_empty())
+  {
+    specs.push(ListenSpec {
+      name: "external-controller".into(),
+      address: normalize_listen(ctrl),
+      protocol: Protocol::Tcp,
+    });
+  }
+  specs
+}
+
+/// 检查配置中的各监听地址是否已被占用
+pub fn check_config(config_path: &Path) -> io::Result<Vec<PortConflict>> {
+  let text = fs::read_to_string(config_path)?;
+  let yaml: serde_yaml::Value =
+    serde_yaml::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+  Ok(check(&listen_specs(&yaml)))
+}
+
+pub fn check(specs: &[ListenSpec]) -> Vec<PortConflict> {
+  let mut conflicts = Vec::new();
+  for spec in specs {
+    let Some(addr) = spec.address.to_socket_addrs().ok().and_then(|mut a| a.next()) else {
+      continue;
+    };
+    let result = match spec.protocol {
+      Protocol::Tcp => TcpListener::bind(addr).map(|_| ()),
+      Protocol::Udp => UdpSocket::bind(addr).map(|_| ()),
+    };
+    let Err(e) = result else { continue };
+    // 低端口无权限不属于冲突，交由内核自身报错
+    if e.kind() != io::ErrorKind::AddrInUse {
+      continue;
+    }
+    let owner = find_owner(addr, spec.protocol);
+    let message = match owner.as_ref() {
+      Some((pid, Some(name))) => format!("{} 的地址 {} 已被 {}（pid {}）占用", spec.name, spec.address, name, pid),
+      Some((pid, None)) => format!("{} 的地址 {} 已被 pid {} 占用", spec.name, spec.address, pid),
+      None => format!("{} 的地址 {} 已被占用", spec.name, spec.address),
+    };
+    conflicts.push(PortConflict {
+      name: spec.name.clone(),
+      address: spec.address.clone(),
+      protocol: spec.protocol,
+      pid: owner.as_ref().map(|(pid, _)| *pid),
+      process: owner.and_then(|(_, name)| name),
+      message,
+    });
+  }
+  conflicts
+}
+
+pub(crate) fn join_host_port(host: &str, port: u16) -> String {
+  if host.contains(':') {
+    format!("[{}]:{}", host, port)
+  } else {
+    format!("{}:{}", host, port)
+  }
+}
+
+/// ":9090" 之类省略主机的写法表示监听全部地址
+fn normalize_listen(listen: &str) -> String {
+  let listen = listen.trim();
+  match listen.rsplit_once(':') {
+    Some(("", port)) => format!("0.0.0.0:{}", port),
+    _ => listen.to_string(),
+  }
+}
+
+/// 通过 /proc/net/* 的 socket inode 反查占用端口的进程
+#[cfg(target_os = "linux")]
+fn find_owner(addr: SocketAddr, protocol: Protocol) -> Option<(u32, Option<String>)> {
+  let tables: &[&str] = match protocol {
+    Protocol::Tcp => &["/proc/net/tcp", "/proc/net/tcp6"],
+    Protocol::Udp => &["/proc/net/udp", "/proc/net/udp6"],
+  };
+  let mut inode = None;
+  'tables: for table in tables {
+    let Ok(text) = fs::read_to_string(table) else { continue };
+    for line in text.lines().skip(1) {
+      let cols: Vec<&str> = line.split_whitespace().collect();
+      if cols.len() < 10 {
+        continue;
+      }
+      let Some(local) = parse_proc_addr(cols[1]) else { continue };
+      // TCP 仅看 LISTEN(0A) 状态
+      let listening = protocol == Protocol::Udp || cols[3] == "0A";
+      if addr_matches(local, addr) && listening && cols[9] != "0" {
+        inode = Some(cols[9].to_string());
+        break 'tables;
+      }
+    }
+  }
+  let target = format!("socket:[{}]", inode?);
+  for entry in fs::read_dir("/proc").ok()?.flatten() {
+    let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else { continue };
+    let Ok(fds) = fs::read_dir(entry.path().join("fd")) else { continue };
+    for fd in fds.flatten() {
+      if fs::read_link(fd.path()).map(|l| l.to_string_lossy() == target).unwrap_or(false) {
+        let name = fs::read_to_string(entry.path().join("comm")).ok().map(|s| s.trim().to_string());
+        return Some((pid, name));
+      }
+    }
+  }
+  None
+}
+
+/// 解析 /proc/net/* 中 "0100007F:1F90" 形式的本地地址；IP 按内核的主机字节序逐个 32 位字输出
+#[cfg(target_os = "linux")]
+fn parse_proc_addr(field: &str) -> Option<SocketAddr> {
+  use std::net::IpAddr;
+  let (ip_hex, port_hex) = field.split_once(':')?;
+  let port = u16::from_str_radix(port_hex, 16).ok()?;
+  let mut bytes = Vec::with_capacity(16);
+  for i in (0..ip_hex.len()).step_by(8) {
+    let word = u32::from_str_radix(ip_hex.get(i..i + 8)?, 16).ok()?;
+    bytes.extend_from_slice(&word.to_ne_bytes());
+  }
+  let ip = match bytes.len() {
+    4 => IpAddr::from(<[u8; 4]>::try_from(bytes).ok()?),
+    16 => IpAddr::from(<[u8; 16]>::try_from(bytes).ok()?),
+    _ => return None,
+  };
+  Some(SocketAddr::new(ip, port))
+}
+
+/// 端口相同且地址相同；任一方为 0.0.0.0 或 :: 时视为通配，IPv4 映射地址按 IPv4 比较
+#[cfg(target_os = "linux")]
+fn addr_matches(local: SocketAddr, target: SocketAddr) -> bool {
+  use std::net::IpAddr;
+  let canonical = |ip: IpAddr| match ip {
+    IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+    v4 => v4,
+  };
+  let (a, b) = (canonical(local.ip()), canonical(target.ip()));
+  local.port() == target.port() && (a.is_unspecified() || b.is_unspecified() || a == b)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn find_owner(_addr: SocketAddr, _protocol: Protocol) -> Option<(u32, Option<String>)> {
+  None
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+  use super::*;
+
+  fn native_hex(ip: [u8; 4]) -> String {
+    format!("{:08X}", u32::from_ne_bytes(ip))
+  }
+
+  #[test]
+  fn parses_proc_addresses() {
+    let v4 = format!("{}:1F90", native_hex([127, 0, 0, 1]));
+    assert_eq!(parse_proc_addr(&v4), Some("127.0.0.1:8080".parse().unwrap()));
+    let v6 = format!("0000000000000000{}{}:0035", native_hex([0, 0, 0xff, 0xff]), native_hex([10, 0, 0, 2]));
+    assert_eq!(parse_proc_addr(&v6), Some("[::ffff:10.0.0.2]:53".parse().unwrap()));
+    assert_eq!(parse_proc_addr("0100:1F90"), None);
+  }
+
+  #[test]
+  fn matches_address_with_wildcards() {
+    let addr = |s: &str| s.parse::<SocketAddr>().unwrap();
+    assert!(addr_matches(addr("127.0.0.1:7890"), addr("127.0.0.1:7890")));
+    assert!(!addr_matches(addr("192.168.1.2:7890"), addr("127.0.0.1:7890")));
+    assert!(!addr_matches(addr("127.0.0.1:7891"), addr("127.0.0.1:7890")));
+    assert!(addr_matches(addr("0.0.0.0:7890"), addr("127.0.0.1:7890")));
+    assert!(addr_matches(addr("[::]:7890"), addr("127.0.0.1:7890")));
+    assert!(addr_matches(addr("192.168.1.2:7890"), addr("0.0.0.0:7890")));
+    assert!(addr_matches(addr("[::ffff:127.0.0.1]:7890"), addr("127.0.0.1:7890")));
+    assert!(!addr_matches(addr("[::1]:7890"), addr("127.0.0.1:7890")));
+  }
+}
@@ -0,0 +1,141 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// 将内核放入独立进程组，便于整组发送信号（含其派生的子进程）
+pub fn isolate_process_group(cmd: &mut Command) {
+  #[cfg(unix)]
+  {
+    use std::os::unix::process::CommandExt;
+    cmd.process_group(0);
+  }
+  #[cfg(not(unix))]
+  {
+    let _ = cmd;
+  }
+}
+
+/// 请求进程组自行退出；返回 false 表示平台不支持或发送失败
+pub fn terminate_group(pid: u32) -> bool {
+  #[cfg(unix)]
+  {
+    signal_group(pid, libc::SIGTERM)
+  }
+  #[cfg(not(unix))]
+  {
+    let _ = pid;
+    false
+  }
+}
+
+/// 强制结束整个进程组
+pub fn kill_group(pid: u32) -> bool {
+  #[cfg(unix)]
+  {
+    signal_group(pid, libc::SIGKILL)
+  }
+  #[cfg(windows)]
+  {
+    Command::new("taskkill")
+      .args(["/PID", &pid.to_string(), "/T", "/F"])
+      .status()
+      .map(|s| s.success())
+      .unwrap_or(false)
+  }
+}
+
+#[cfg(unix)]
+fn signal_group(pid: u32, sig: libc::c_int) -> bool {
+  // SAFETY: 负 pid 表示向进程组发送信号；内核以 process_group(0) 启动，pgid 与 pid 相同
+  unsafe { libc::kill(-(pid as libc::pid_t), sig) == 0 }
+}
+
+pub fn is_alive(pid: u32) -> bool {
+  #[cfg(unix)]
+  {
+    // SAFETY: 信号 0 仅检测进程是否存在
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+  }
+  #[cfg(windows)]
+  {
+    Command::new("tasklist")
+      .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+      .output()
+      .map(|o| String::from_utf8_lossy(&o.stdout).contains(&pid.to_string()))
+      .unwrap_or(false)
+  }
+}
+
+/// 判断 pid 是否仍是 mihomo 内核，避免 pid 被复用后误杀其他进程；无法确认时视为不是
+fn looks_like_core(pid: u32) -> bool {
+  process_name(pid).map(|name| is_core_name(&name)).unwrap_or(false)
+}
+
+fn is_core_name(name: &str) -> bool {
+  // macOS 的 ps 输出完整路径，只比较文件名
+  let file = name.trim().rsplit(['/', '\\']).next().unwrap_or("");
+  file.to_ascii_lowercase().starts_with("mihomo")
+}
+
+/// 进程的可执行文件名，查询失败时为 None
+fn process_name(pid: u32) -> Option<String> {
+  #[cfg(target_os = "linux")]
+  {
+    fs::read_to_string(format!("/proc/{}/comm", pid)).ok()
+  }
+  #[cfg(all(unix, not(target_os = "linux")))]
+  {
+    let out = Command::new("ps").args(["-p", &pid.to_string(), "-o", "comm="]).output().ok()?;
+    let name = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (out.status.success() && !name.is_empty()).then_some(name)
+  }
+  #[cfg(windows)]
+  {
+    // CSV 格式："mihomo.exe","1234",...；没有匹配的进程时输出提示信息而非 CSV
+    let out = Command::new("tasklist")
+      .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+      .output()
+      .ok()?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    let mut fields = text.lines().next()?.split(',').map(|f| f.trim().trim_matches('"'));
+    let name = fields.next()?.to_string();
+    (fields.next()? == pid.to_string()).then_some(name)
+  }
+}
+
+pub fn write_pid_file(path: &Path, pid: u32) {
+  if let Some(parent) = path.parent() {
+    let _ = fs::create_dir_all(parent);
+  }
+  if let Err(e) = fs::write(path, format!("{}\n", pid)) {
+    log::warn!(target: "mihomo-core", "写入 PID 文件失败: {}", e);
+  }
+}
+
+pub fn remove_pid_file(path: &Path) {
+  let _ = fs::remove_file(path);
+}
+
+/// 查找上次会话遗留的内核进程并结束它，返回被清理的 pid
+pub async fn reap_stale(path: &Path) -> Option<u32> {
+  let text = fs::read_to_string(path).ok()?;
+  remove_pid_file(path);
+  let pid: u32 = text.lines().next()?.trim().parse().ok()?;
+  if !is_alive(pid) || !looks_like_core(pid) {
+    return None;
+  }
+
+  log::warn!(target: "mihomo-core", "发现上次会话遗留的内核进程 pid={}，正在结束", pid);
+  if terminate_group(pid) {
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_secs(3) {
+      if !is_alive(pid) {
+        return Some(pid);
+      }
+      tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+  }
+  kill_group(pid);
+  Some(pid)
+}
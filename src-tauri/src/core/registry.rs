@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::manager::{CoreManager, CoreStatusEvent};
+use super::ports::{self, ListenSpec};
+use super::process;
+use super::version;
+use crate::config::settings::Settings;
+
+/// 未指定实例 id 时使用的实例
+pub const DEFAULT_INSTANCE: &str = "default";
+
+pub type SharedCore = Arc<tauri::async_runtime::Mutex<CoreManager>>;
+
+/// 实例列表中的一项
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceInfo {
+  #[serde(flatten)]
+  pub state: CoreStatusEvent,
+  pub core_path: Option<String>,
+  /// profile 指定的内核版本
+  pub core_version: Option<String>,
+  /// 原始 profile 配置
+  pub source_config: Option<String>,
+  /// 实际传给内核的运行时配置
+  pub runtime_config: Option<String>,
+  pub work_dir: Option<String>,
+  /// 运行时配置中声明的监听地址
+  pub listen: Vec<ListenSpec>,
+}
+
+/// 按 id 管理的多个内核实例，每个实例拥有独立的进程、配置、工作目录、日志与 pid 文件
+#[derive(Debug, Default)]
+pub struct CoreRegistry {
+  instances: BTreeMap<String, SharedCore>,
+  /// 数据根目录，pid 文件与日志目录据此生成
+  data_root: Option<PathBuf>,
+  /// 新实例默认使用的内核可执行文件
+  default_core_path: Option<PathBuf>,
+  /// 已安装内核版本所在目录
+  cores_dir: Option<PathBuf>,
+  /// 新实例使用的重启策略、超时与日志文件设置
+  settings: Settings,
+  app_handle: Option<AppHandle>,
+}
+
+impl CoreRegistry {
+  /// 按设置确定数据目录与实例默认选项，绑定应用句柄并创建默认实例
+  pub async fn configure(&mut self, settings: &Settings, core_path: Option<PathBuf>, app: AppHandle) {
+    let data_root = settings.paths.data_dir.clone();
+    // 其他实例按需创建，先清理它们上次会话遗留的进程
+    if let Ok(entries) = std::fs::read_dir(data_root.join("instances")) {
+      for entry in entries.flatten() {
+        if let Some(pid) = process::reap_stale(&entry.path().join("core.pid")).await {
+          log::info!("terminated stale core process pid={} instance={:?}", pid, entry.file_name());
+        }
+      }
+    }
+    self.data_root = Some(data_root);
+    self.default_core_path = core_path;
+    self.cores_dir = Some(settings.paths.cores_dir());
+    self.settings = settings.clone();
+    self.app_handle = Some(app);
+    let Ok(core) = self.get_or_create(None) else { return };
+    let reaped = core.lock().await.reap_stale_core().await;
+    if let Some(pid) = reaped {
+      log::info!("terminated stale core process pid={} instance={}", pid, DEFAULT_INSTANCE);
+    }
+  }
+
+  /// 将设置中的重启策略、超时与日志文件配置应用到所有实例；cores 目录变化时同步切换默认内核与版本解析目录，
+  /// 与 VersionManager 保持一致（运行中的实例在重启后使用新目录）
+  pub async fn apply_settings(&mut self, settings: &Settings) {
+    self.settings = settings.clone();
+    let cores_dir = settings.paths.cores_dir();
+    let old_default = if self.cores_dir.as_deref() != Some(cores_dir.as_path()) {
+      self.cores_dir = Some(cores_dir.clone());
+      Some(self.default_core_path.replace(version::current_core_path(&cores_dir)))
+    } else {
+      None
+    };
+    for core in self.instances.values() {
+      let mut mgr = core.lock().await;
+      apply_to(&mut mgr, settings);
+      if let Some(old) = old_default.as_ref() {
+        mgr.cores_dir = self.cores_dir.clone();
+        if mgr.core_path.is_none() || mgr.core_path == *old {
+          mgr.core_path = self.default_core_path.clone();
+        }
+      }
+    }
+  }
+
+  pub fn default_core_path(&self) -> Option<PathBuf> {
+    self.default_core_path.clone()
+  }
+
+  /// 更新默认内核；仍使用旧默认内核的实例一并切换，下次启动生效
+  pub async fn set_default_core_path(&mut self, path: PathBuf) {
+    let old = self.default_core_path.replace(path.clone());
+    for core in self.instances.values() {
+      let mut mgr = core.lock().await;
+      if mgr.core_path.is_none() || mgr.core_path == old {
+        mgr.set_core_path(path.clone());
+      }
+    }
+  }
+
+  pub fn get(&self, id: Option<&str>) -> Result<SharedCore, String> {
+    let id = id.unwrap_or(DEFAULT_INSTANCE);
+    self.instances.get(id).cloned().ok_or_else(|| format!("内核实例 {} 不存在", id))
+  }
+
+  /// 获取实例，不存在时以默认设置创建。id 直接用作 instances/ 下的目录名，只允许 ASCII 字母、数字、- 与 _，
+  /// 且不能与已有实例仅大小写不同，避免不同 id 在不区分大小写的文件系统上共用 pid 文件与日志目录
+  pub fn get_or_create(&mut self, id: Option<&str>) -> Result<SharedCore, String> {
+    let id = id.unwrap_or(DEFAULT_INSTANCE);
+    if let Some(core) = self.instances.get(id) {
+      return Ok(core.clone());
+    }
+    if !valid_id(id) {
+      return Err(format!("无效的实例 id: {}，只能包含字母、数字、- 与 _", id));
+    }
+    if let Some(other) = self.instances.keys().find(|k| k.eq_ignore_ascii_case(id)) {
+      return Err(format!("实例 id {} 与已有实例 {} 仅大小写不同", id, other));
+    }
+    let mut mgr = CoreManager::default();
+    mgr.instance = id.to_string();
+    if let Some(path) = self.default_core_path.clone() {
+      mgr.set_core_path(path);
+    }
+    mgr.cores_dir = self.cores_dir.clone();
+    apply_to(&mut mgr, &self.settings);
+    if let Some(root) = self.data_root.as_ref() {
+      let (pid_file, log_dir) = instance_paths(root, id);
+      mgr.set_pid_file(pid_file);
+      mgr.log_files.set_dir(log_dir);
+    }
+    if let Some(app) = self.app_handle.clone() {
+      mgr.attach_app_handle(app);
+    }
+    let core: SharedCore = Arc::new(tauri::async_runtime::Mutex::new(mgr));
+    self.instances.insert(id.to_string(), core.clone());
+    Ok(core)
+  }
+
+  /// 删除已停止的实例；默认实例不可删除
+  pub async fn remove(&mut self, id: &str) -> Result<(), String> {
+    if id == DEFAULT_INSTANCE {
+      return Err("默认实例不可删除".into());
+    }
+    let core = self.get(Some(id))?;
+    if core.lock().await.child.is_some() {
+      return Err(format!("内核实例 {} 正在运行，请先停止", id));
+    }
+    self.instances.remove(id);
+    Ok(())
+  }
+
+  pub fn all(&self) -> Vec<SharedCore> {
+    self.instances.values().cloned().collect()
+  }
+
+  /// 查找以该 profile 运行中的其他实例，避免两个内核共用同一工作目录
+  pub async fn running_with_source(&self, source: &Path, except: &str) -> Option<String> {
+    for (id, core) in self.instances.iter() {
+      if id == except {
+        continue;
+      }
+      let mgr = core.lock().await;
+      if mgr.child.is_some() && mgr.source_config.as_deref() == Some(source) {
+        return Some(id.clone());
+      }
+    }
+    None
+  }
+
+  /// 查找使用该工作目录运行中的实例
+  pub async fn running_in_work_dir(&self, dir: &Path) -> Option<String> {
+    for (id, core) in self.instances.iter() {
+      let mgr = core.lock().await;
+      if mgr.child.is_some() && mgr.work_dir.as_deref() == Some(dir) {
+        return Some(id.clone());
+      }
+    }
+    None
+  }
+
+  pub async fn list(&self) -> Vec<InstanceInfo> {
+    let mut list = Vec::with_capacity(self.instances.len());
+    for core in self.instances.values() {
+      let mut mgr = core.lock().await;
+      mgr.get_status();
+      let listen = mgr
+        .current_config
+        .as_deref()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|t| serde_yaml::from_str::<serde_yaml::Value>(&t).ok())
+        .map(|y| ports::listen_specs(&y))
+        .unwrap_or_default();
+      list.push(InstanceInfo {
+        state: mgr.status_event(None),
+        core_path: mgr.core_path.as_ref().map(|p| p.to_string_lossy().to_string()),
+        core_version: mgr.core_version.clone(),
+        source_config: mgr.source_config.as_ref().map(|p| p.to_string_lossy().to_string()),
+        runtime_config: mgr.current_config.as_ref().map(|p| p.to_string_lossy().to_string()),
+        work_dir: mgr.work_dir.as_ref().map(|p| p.to_string_lossy().to_string()),
+        listen,
+      });
+    }
+    list
+  }
+}
+
+fn apply_to(mgr: &mut CoreManager, settings: &Settings) {
+  mgr.set_restart_config(settings.core.restart.clone());
+  mgr.stop_grace_ms = Some(settings.core.stop_grace_ms);
+  mgr.ready_timeout_ms = Some(settings.core.ready_timeout_ms);
+  mgr.log_files.set_config(settings.log_file.clone());
+}
+
+/// 默认实例沿用 core.pid 与 logs/，其他实例放在 instances/<id>/ 下
+fn instance_paths(root: &Path, id: &str) -> (PathBuf, PathBuf) {
+  if id == DEFAULT_INSTANCE {
+    return (root.join("core.pid"), root.join("logs"));
+  }
+  let dir = root.join("instances").join(id);
+  (dir.join("core.pid"), dir.join("logs"))
+}
+
+fn valid_id(id: &str) -> bool {
+  !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rejects_ids_that_would_share_an_instance_dir() {
+    let mut reg = CoreRegistry::default();
+    assert!(reg.get_or_create(None).is_ok());
+    assert!(reg.get_or_create(Some("work_1")).is_ok());
+    for id in ["", "a.b", "../x", "a b", "工作", "WORK_1"] {
+      assert!(reg.get_or_create(Some(id)).is_err(), "{id:?} should be rejected");
+    }
+    assert_eq!(reg.all().len(), 2);
+  }
+}
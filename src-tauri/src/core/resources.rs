@@ -0,0 +1,183 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::registry::CoreRegistry;
+use super::util::unix_millis;
+
+type Shared<T> = tauri::async_runtime::Mutex<T>;
+
+/// 资源采样推送事件名
+pub const CORE_RESOURCE_EVENT: &str = "core_resource_usage";
+
+/// 采样间隔
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 保留的采样点数量（约 10 分钟）
+pub const DEFAULT_SAMPLE_CAPACITY: usize = 300;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceSample {
+  /// 毫秒级 Unix 时间戳
+  pub timestamp: u64,
+  pub pid: u32,
+  /// 累计 CPU 时间（用户态 + 内核态）
+  pub cpu_time_ms: u64,
+  /// 与上一个采样点相比的 CPU 占用，100 表示占满一个核；首个点为 None
+  pub cpu_percent: Option<f64>,
+  pub rss_bytes: u64,
+  pub threads: u32,
+  /// 打开的文件描述符数，无权限读取时为 None
+  pub fds: Option<u32>,
+}
+
+/// core_resource_usage 事件负载，附带实例 id
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceEvent {
+  pub instance: String,
+  #[serde(flatten)]
+  pub sample: ResourceSample,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceUsage {
+  pub interval_ms: u64,
+  /// 按时间正序
+  pub samples: Vec<ResourceSample>,
+}
+
+#[derive(Debug)]
+struct Series {
+  samples: VecDeque<ResourceSample>,
+  capacity: usize,
+  /// 上一次采样的单调时钟，用于计算 CPU 占用
+  last_at: Option<Instant>,
+}
+
+/// 内核进程资源占用的有界时间序列，进程变化时清空
+#[derive(Debug)]
+pub struct ResourceHistory {
+  inner: Mutex<Series>,
+}
+
+impl Default for ResourceHistory {
+  fn default() -> Self {
+    Self::with_capacity(DEFAULT_SAMPLE_CAPACITY)
+  }
+}
+
+impl ResourceHistory {
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self {
+      inner: Mutex::new(Series { samples: VecDeque::with_capacity(capacity), capacity, last_at: None }),
+    }
+  }
+
+  /// 采样一次并加入序列；进程不存在或平台不支持时返回 None
+  pub fn sample(&self, pid: u32) -> Option<ResourceSample> {
+    let stat = read_proc(pid)?;
+    let now = Instant::now();
+    let mut series = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+    if series.samples.back().map(|s| s.pid != pid).unwrap_or(false) {
+      series.samples.clear();
+      series.last_at = None;
+    }
+    let cpu_percent = match (series.samples.back(), series.last_at) {
+      (Some(prev), Some(at)) => {
+        let wall = now.duration_since(at).as_millis() as f64;
+        let cpu = stat.cpu_time_ms.saturating_sub(prev.cpu_time_ms) as f64;
+        (wall > 0.0).then(|| (cpu / wall * 1000.0).round() / 10.0)
+      }
+      _ => None,
+    };
+    let sample = ResourceSample {
+      timestamp: unix_millis(),
+      pid,
+      cpu_time_ms: stat.cpu_time_ms,
+      cpu_percent,
+      rss_bytes: stat.rss_bytes,
+      threads: stat.threads,
+      fds: stat.fds,
+    };
+    if series.samples.len() >= series.capacity {
+      series.samples.pop_front();
+    }
+    series.samples.push_back(sample.clone());
+    series.last_at = Some(now);
+    Some(sample)
+  }
+
+  pub fn snapshot(&self) -> Vec<ResourceSample> {
+    let series = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+    series.samples.iter().cloned().collect()
+  }
+
+  pub fn clear(&self) {
+    let mut series = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+    series.samples.clear();
+    series.last_at = None;
+  }
+}
+
+/// 启动后台采样任务：内核运行时周期读取其资源占用并推送事件
+pub fn spawn(app: AppHandle) {
+  tauri::async_runtime::spawn(async move {
+    loop {
+      tokio::time::sleep(SAMPLE_INTERVAL).await;
+      let cores = app.state::<Shared<CoreRegistry>>().lock().await.all();
+      for core in cores {
+        let (instance, pid, history) = {
+          let mgr = core.lock().await;
+          (mgr.instance.clone(), mgr.child.as_ref().map(|c| c.id()), mgr.resources.clone())
+        };
+        let Some(pid) = pid else { continue };
+        if let Some(sample) = history.sample(pid) {
+          let _ = app.emit(CORE_RESOURCE_EVENT, ResourceEvent { instance, sample });
+        }
+      }
+    }
+  });
+}
+
+struct ProcStat {
+  cpu_time_ms: u64,
+  rss_bytes: u64,
+  threads: u32,
+  fds: Option<u32>,
+}
+
+#[cfg(target_os = "linux")]
+fn read_proc(pid: u32) -> Option<ProcStat> {
+  let base = std::path::PathBuf::from(format!("/proc/{}", pid));
+  // comm 可能含空格或括号，从最后一个 ')' 之后开始按空白切分；utime/stime 为第 14、15 项
+  let stat = std::fs::read_to_string(base.join("stat")).ok()?;
+  let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+  let utime: u64 = fields.get(11)?.parse().ok()?;
+  let stime: u64 = fields.get(12)?.parse().ok()?;
+  // SAFETY: sysconf 只读取系统常量，不涉及内存访问
+  let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+  // 查询失败返回 -1（理论上不会为 0），此时使用 Linux 的默认值 USER_HZ = 100，避免除以 0
+  let ticks = u64::try_from(ticks).ok().filter(|t| *t > 0).unwrap_or(100);
+
+  let status = std::fs::read_to_string(base.join("status")).ok()?;
+  let field = |key: &str| {
+    status
+      .lines()
+      .find_map(|l| l.strip_prefix(key))
+      .and_then(|v| v.split_whitespace().next())
+      .and_then(|v| v.parse::<u64>().ok())
+  };
+  let rss_kb = field("VmRSS:").unwrap_or(0);
+  let threads = field("Threads:").unwrap_or(0) as u32;
+  let fds = std::fs::read_dir(base.join("fd")).ok().map(|d| d.count() as u32);
+
+  Some(ProcStat { cpu_time_ms: (utime + stime) * 1000 / ticks, rss_bytes: rss_kb * 1024, threads, fds })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_proc(_pid: u32) -> Option<ProcStat> {
+  None
+}
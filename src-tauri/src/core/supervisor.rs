@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use tauri::{AppHandle, Manager};
+
+use super::manager;
+use super::registry::CoreRegistry;
+
+type Shared<T> = tauri::async_runtime::Mutex<T>;
+
+/// 监督任务的轮询间隔
+const SUPERVISE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 启动后台监督任务：周期检查各实例的内核子进程，记录退出状态并按策略自动重启；
+/// 重启后的就绪等待放在独立任务中，不占用实例锁也不拖慢其他实例的检查
+pub fn spawn(app: AppHandle) {
+  tauri::async_runtime::spawn(async move {
+    loop {
+      tokio::time::sleep(SUPERVISE_INTERVAL).await;
+      let cores = app.state::<Shared<CoreRegistry>>().lock().await.all();
+      for core in cores {
+        let probe = {
+          let mut mgr = core.lock().await;
+          mgr.check_exit();
+          mgr.due_restart()
+        };
+        if let Some(probe) = probe {
+          tauri::async_runtime::spawn(async move {
+            if let Err(e) = manager::wait_ready(&core, probe).await {
+              log::warn!(target: "mihomo-core", "自动重启失败: {}", e);
+            }
+          });
+        }
+      }
+    }
+  });
+}
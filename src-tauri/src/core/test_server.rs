@@ -0,0 +1,132 @@
+//! 测试用的本地 HTTP 服务器：每个连接只处理一个请求，回复后关闭连接
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// 收到的请求
+#[derive(Debug, Clone)]
+pub struct Request {
+  /// 路径与查询串，如 /repos/o/r/releases?per_page=2&page=1
+  pub path: String,
+  /// 小写的头名与值
+  pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self.headers.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+  }
+}
+
+/// 回复；cut_after 为 Some(n) 时声明完整长度但只发送 body 的前 n 字节后断开
+#[derive(Debug, Clone, Default)]
+pub struct Reply {
+  pub status: u16,
+  pub headers: Vec<(String, String)>,
+  pub body: Vec<u8>,
+  pub cut_after: Option<usize>,
+}
+
+impl Reply {
+  pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+    Self { status, body: body.into(), ..Default::default() }
+  }
+
+  pub fn header(mut self, name: &str, value: &str) -> Self {
+    self.headers.push((name.to_string(), value.to_string()));
+    self
+  }
+
+  pub fn cut_after(mut self, n: usize) -> Self {
+    self.cut_after = Some(n);
+    self
+  }
+}
+
+pub struct TestServer {
+  /// http://127.0.0.1:<port>
+  pub base: String,
+  requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl TestServer {
+  /// 在随机端口上启动，handler 按请求决定回复；服务线程随测试进程结束
+  pub fn start(handler: impl Fn(&Request) -> Reply + Send + Sync + 'static) -> Self {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind test server");
+    let base = format!("http://{}", listener.local_addr().expect("local addr"));
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let handler = Arc::new(handler);
+    let seen = requests.clone();
+    thread::spawn(move || {
+      for stream in listener.incoming().flatten() {
+        let handler = handler.clone();
+        let seen = seen.clone();
+        thread::spawn(move || {
+          if let Some(req) = read_request(&stream) {
+            let reply = handler(&req);
+            seen.lock().unwrap().push(req);
+            write_reply(stream, &reply);
+          }
+        });
+      }
+    });
+    Self { base, requests }
+  }
+
+  pub fn url(&self, path: &str) -> String {
+    format!("{}{}", self.base, path)
+  }
+
+  /// 已处理的请求，按完成顺序
+  pub fn requests(&self) -> Vec<Request> {
+    self.requests.lock().unwrap().clone()
+  }
+}
+
+fn read_request(stream: &TcpStream) -> Option<Request> {
+  let mut reader = BufReader::new(stream);
+  let mut line = String::new();
+  reader.read_line(&mut line).ok()?;
+  let path = line.split_whitespace().nth(1)?.to_string();
+  let mut headers = Vec::new();
+  loop {
+    line.clear();
+    if reader.read_line(&mut line).ok()? == 0 || line.trim().is_empty() {
+      break;
+    }
+    if let Some((k, v)) = line.split_once(':') {
+      headers.push((k.trim().to_ascii_lowercase(), v.trim().to_string()));
+    }
+  }
+  Some(Request { path, headers })
+}
+
+fn write_reply(mut stream: TcpStream, reply: &Reply) {
+  let mut head = format!("HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n", reply.status, reason(reply.status), reply.body.len());
+  for (k, v) in &reply.headers {
+    head.push_str(&format!("{}: {}\r\n", k, v));
+  }
+  head.push_str("\r\n");
+  let body = match reply.cut_after {
+    Some(n) => &reply.body[..n.min(reply.body.len())],
+    None => &reply.body[..],
+  };
+  let _ = stream.write_all(head.as_bytes());
+  let _ = stream.write_all(body);
+  let _ = stream.flush();
+}
+
+fn reason(status: u16) -> &'static str {
+  match status {
+    200 => "OK",
+    206 => "Partial Content",
+    403 => "Forbidden",
+    404 => "Not Found",
+    416 => "Range Not Satisfiable",
+    429 => "Too Many Requests",
+    500 => "Internal Server Error",
+    _ => "Status",
+  }
+}
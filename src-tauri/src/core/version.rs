@@ -1,15 +1,47 @@
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 use std::{env, fs, io, path::{Path, PathBuf}};
-use std::io::Cursor;
+use std::io::{Read, Seek};
 
-#[derive(Debug, Copy, Clone, Serialize)]
+use super::mirror::{Mirror, MirrorHealthTable, MirrorStatus};
+use crate::config::settings::Settings;
+
+/// 默认的 GitHub API 地址
+pub const DEFAULT_API_BASE: &str = "https://api.github.com";
+
+/// 升级失败回滚事件名
+pub const CORE_ROLLED_BACK_EVENT: &str = "core_rolled_back";
+
+/// 记录切换前 current 指向的版本名，位于 cores/ 下
+const PREVIOUS_FILE: &str = "previous";
+
+/// 每个渠道默认保留的版本数
+pub const DEFAULT_KEEP_VERSIONS: usize = 3;
+
+/// 历史版本列表每页的默认与最大数量
+pub const DEFAULT_RELEASES_PER_PAGE: u32 = 20;
+const MAX_RELEASES_PER_PAGE: u32 = 100;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ReleaseChannel {
   Stable,
   Dev,
 }
 
+impl ReleaseChannel {
+  /// 按版本号推断渠道：稳定版形如 v1.19.0，开发版为 alpha-xxxx 等其他形式
+  pub fn of_version(version: &str) -> Self {
+    let mut chars = version.chars();
+    let stable = matches!(chars.next(), Some('v' | 'V'))
+      && chars.next().map(|c| c.is_ascii_digit()).unwrap_or(false)
+      && !version.contains("alpha");
+    if stable { ReleaseChannel::Stable } else { ReleaseChannel::Dev }
+  }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct VersionInfo {
   pub version: String,
@@ -19,12 +51,83 @@ pub struct VersionInfo {
   pub channel: ReleaseChannel,
 }
 
+/// 已安装的内核版本
+#[derive(Debug, Clone, Serialize)]
+pub struct InstalledVersion {
+  pub version: String,
+  /// 内核可执行文件路径
+  pub path: String,
+  /// 版本目录占用的字节数
+  pub size_bytes: u64,
+  /// 毫秒级 Unix 时间戳，取版本目录的创建时间（不支持时为修改时间）
+  pub installed_at: Option<u64>,
+  /// `mihomo -v` 输出的首行，无法执行时为 None
+  pub version_output: Option<String>,
+  /// cores/current 是否指向该版本
+  pub current: bool,
+  /// 是否有内核实例正以该版本运行
+  pub running: bool,
+}
+
+/// 发布列表中的一项
+#[derive(Debug, Clone, Serialize)]
+pub struct ReleaseSummary {
+  pub tag: String,
+  pub name: Option<String>,
+  pub published_at: Option<String>,
+  pub prerelease: bool,
+  /// 匹配当前平台与架构的资产名，没有时为 None
+  pub platform_asset: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReleasePage {
+  pub page: u32,
+  pub per_page: u32,
+  pub releases: Vec<ReleaseSummary>,
+  pub has_more: bool,
+}
+
+/// core_rolled_back 事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreRolledBackEvent {
+  /// 回滚前的版本
+  pub from: String,
+  /// 回滚后的版本
+  pub to: String,
+  pub reason: String,
+  /// 以回滚后的内核重新启动的实例
+  pub restarted: Vec<String>,
+}
+
+/// 旧版本清理中的一项
+#[derive(Debug, Clone, Serialize)]
+pub struct GcEntry {
+  pub version: String,
+  pub channel: ReleaseChannel,
+  pub size_bytes: u64,
+}
+
+/// 旧版本清理结果；dry_run 时 removed 为将会删除的版本
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GcReport {
+  pub dry_run: bool,
+  pub removed: Vec<GcEntry>,
+  pub freed_bytes: u64,
+  pub kept: Vec<String>,
+  /// 删除失败的版本及原因
+  pub failed: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct DownloadPlan {
   pub version: String,
   pub asset_name: String,
+  /// 资产的原始地址，下载时按所用镜像改写
   pub asset_url: String,
   pub checksum_url: Option<String>,
+  /// 提供该发布的镜像，下载时优先使用
+  pub mirror: Mirror,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -35,23 +138,81 @@ struct Asset {
 
 #[derive(Debug, Deserialize, Clone)]
 struct Release {
+  tag_name: String,
+  name: Option<String>,
   published_at: Option<String>,
+  #[serde(default)]
   prerelease: bool,
+  #[serde(default)]
   assets: Vec<Asset>,
 }
 
+#[derive(Debug, Clone, Copy)]
+enum ReleaseQuery<'a> {
+  Latest,
+  Dev,
+  Tag(&'a str),
+}
+
+impl From<ReleaseChannel> for ReleaseQuery<'_> {
+  fn from(channel: ReleaseChannel) -> Self {
+    match channel {
+      ReleaseChannel::Stable => ReleaseQuery::Latest,
+      ReleaseChannel::Dev => ReleaseQuery::Dev,
+    }
+  }
+}
+
 #[derive(Debug)]
 pub struct VersionManager {
   pub cores_dir: PathBuf,
   pub current_version: Option<String>,
   pub current_core_path: Option<PathBuf>,
+  /// 发布内核的 GitHub 仓库（owner/name）
+  pub repo: String,
+  /// GitHub API 地址
+  pub api_base: String,
+  /// 未指定渠道时使用的渠道
+  pub channel: ReleaseChannel,
+  /// 获取发布与资产的镜像，按顺序尝试
+  pub mirrors: Vec<Mirror>,
+  pub mirror_health: MirrorHealthTable,
+  /// 升级失败时自动回滚
+  pub auto_rollback: bool,
 }
 
 impl VersionManager {
-  pub fn new() -> io::Result<Self> {
-    let dir = install_root();
+  pub fn new(settings: &Settings) -> io::Result<Self> {
+    let dir = settings.paths.cores_dir();
     fs::create_dir_all(&dir)?;
-    Ok(Self { cores_dir: dir, current_version: None, current_core_path: None })
+    Ok(Self {
+      current_version: current_link_version(&dir),
+      cores_dir: dir,
+      current_core_path: None,
+      repo: settings.core.repo.clone(),
+      api_base: settings.core.api_base.clone(),
+      channel: settings.core.channel,
+      mirrors: settings.core.mirrors.clone(),
+      mirror_health: MirrorHealthTable::default(),
+      auto_rollback: settings.core.auto_rollback,
+    })
+  }
+
+  pub fn apply_settings(&mut self, settings: &Settings) {
+    let cores_dir = settings.paths.cores_dir();
+    if cores_dir != self.cores_dir {
+      if let Err(e) = fs::create_dir_all(&cores_dir) {
+        log::warn!("create cores dir {} failed: {}", cores_dir.display(), e);
+      }
+      self.current_version = current_link_version(&cores_dir);
+      self.current_core_path = None;
+      self.cores_dir = cores_dir;
+    }
+    self.repo = settings.core.repo.clone();
+    self.api_base = settings.core.api_base.clone();
+    self.channel = settings.core.channel;
+    self.mirrors = settings.core.mirrors.clone();
+    self.auto_rollback = settings.core.auto_rollback;
   }
 
   pub fn latest_stub(channel: ReleaseChannel) -> VersionInfo {
@@ -72,8 +233,8 @@ impl VersionManager {
     self.current_core_path = Some(core_path);
   }
 
-  pub async fn fetch_latest(&self, channel: ReleaseChannel) -> Result<VersionInfo> {
-    let (rel, version) = self.get_release_and_version(channel).await?;
+  pub async fn fetch_latest(&self, client: &reqwest::Client, channel: ReleaseChannel) -> Result<VersionInfo> {
+    let (rel, version) = self.get_release_and_version(client, channel).await?;
     Ok(VersionInfo {
       version,
       release_date: rel.published_at,
@@ -83,38 +244,121 @@ impl VersionManager {
     })
   }
 
-  pub async fn download_install_latest(&mut self, channel: ReleaseChannel) -> Result<PathBuf> {
-    let (rel, version) = self.get_release_and_version(channel.clone()).await?;
-
-    let client = build_gh_client()?;
+  /// 按设置顺序尝试的镜像；未配置时直连
+  pub fn mirror_list(&self) -> Vec<Mirror> {
+    if self.mirrors.is_empty() {
+      vec![Mirror::Direct]
+    } else {
+      self.mirrors.clone()
+    }
+  }
 
-    // 选择目标资产
-    let asset = select_target_asset(&rel).context("未找到匹配当前平台与架构的资产")?;
+  pub fn mirror_status(&self) -> Vec<MirrorStatus> {
+    self.mirror_health.status(&self.mirror_list())
+  }
 
-    // 可选校验文件
-    let checksum_asset = rel
-      .assets
-      .iter()
-      .find(|a| a.name.to_ascii_lowercase().contains("sha256") || a.name.to_ascii_lowercase().contains("checksum"))
-      .cloned();
+  /// 下载 origin 的途径及改写后的地址：first 在前，其余镜像按连续失败次数排序，
+  /// 改写后地址相同的途径只保留一个
+  pub fn download_routes(&self, first: &Mirror, origin: &str) -> Vec<(Mirror, String)> {
+    let mut rest: Vec<MirrorStatus> = self.mirror_status().into_iter().filter(|s| s.mirror != *first).collect();
+    rest.sort_by_key(|s| s.health.consecutive_failures);
+    let mut routes: Vec<(Mirror, String)> = Vec::new();
+    for mirror in std::iter::once(first.clone()).chain(rest.into_iter().map(|s| s.mirror)) {
+      let url = mirror.rewrite(origin);
+      if !routes.iter().any(|(_, u)| *u == url) {
+        routes.push((mirror, url));
+      }
+    }
+    routes
+  }
 
-    let bytes = fetch_bytes(&client, &asset.browser_download_url, &asset.name).await?;
+  fn record_mirror<T>(&self, mirror: &Mirror, started: Instant, result: &Result<T>) {
+    match result {
+      Ok(_) => self.mirror_health.record_success(mirror, started.elapsed()),
+      Err(e) => {
+        log::warn!("mirror {} failed: {:#}", mirror.label(), e);
+        self.mirror_health.record_failure(mirror, &format!("{:#}", e));
+      }
+    }
+  }
 
-    // 如存在校验文件，进行校验
-    if let Some(sum) = checksum_asset {
-      if let Ok(text) = fetch_text(&client, &sum.browser_download_url, &sum.name).await {
-        verify_sha256(&bytes, &text).context("校验和不匹配或解析失败")?;
+  /// 依次通过各镜像查找发布并读取版本号，返回发布（资产为原始地址）、版本号与所用镜像
+  async fn resolve_release(&self, client: &reqwest::Client, query: ReleaseQuery<'_>) -> Result<(Release, String, Mirror)> {
+    let mut errors = Vec::new();
+    for mirror in self.mirror_list() {
+      let started = Instant::now();
+      let result = resolve_from(client, &mirror, &self.api_base, &self.repo, query).await;
+      self.record_mirror(&mirror, started, &result);
+      match result {
+        Ok((rel, version)) => return Ok((rel, version, mirror)),
+        Err(e) => errors.push(format!("{}: {:#}", mirror.label(), e)),
       }
     }
+    Err(anyhow!("所有镜像均失败:\n{}", errors.join("\n")))
+  }
 
-    // 安装目录：~/.cache/mihomo-gui/cores/<version>
-    let install_dir = self.cores_dir.join(&version);
-    fs::create_dir_all(&install_dir)?;
+  async fn get_release_and_version(&self, client: &reqwest::Client, channel: ReleaseChannel) -> Result<(Release, String)> {
+    let (rel, version, _) = self.resolve_release(client, ReleaseQuery::from(channel)).await?;
+    Ok((rel, version))
+  }
 
-    let bin_name = target_bin_name();
-    let installed_path = install_asset_bytes(&bytes, &asset.name, &install_dir, &bin_name)?;
+  /// 按页列出发布，page 从 1 开始
+  pub async fn list_releases(&self, client: &reqwest::Client, page: u32, per_page: u32) -> Result<ReleasePage> {
+    let page = page.max(1);
+    let per_page = per_page.clamp(1, MAX_RELEASES_PER_PAGE);
+    let mut errors = Vec::new();
+    for mirror in self.mirror_list() {
+      let started = Instant::now();
+      let result = fetch_release_page(client, &mirror, &self.api_base, &self.repo, page, per_page).await;
+      self.record_mirror(&mirror, started, &result);
+      let (list, has_more) = match result {
+        Ok(r) => r,
+        Err(e) => {
+          errors.push(format!("{}: {:#}", mirror.label(), e));
+          continue;
+        }
+      };
+      let releases = list
+        .into_iter()
+        .map(|rel| ReleaseSummary {
+          platform_asset: select_target_asset(&rel).map(|a| a.name),
+          tag: rel.tag_name,
+          name: rel.name.filter(|n| !n.is_empty()),
+          published_at: rel.published_at,
+          prerelease: rel.prerelease,
+        })
+        .collect();
+      return Ok(ReleasePage { page, per_page, releases, has_more });
+    }
+    Err(anyhow!("所有镜像均失败:\n{}", errors.join("\n")))
+  }
+
+  pub async fn plan_download(&self, client: &reqwest::Client, channel: ReleaseChannel) -> Result<DownloadPlan> {
+    let (rel, version, mirror) = self.resolve_release(client, ReleaseQuery::from(channel)).await?;
+    plan_for_release(&rel, version, mirror)
+  }
 
-    // 设置可执行权限（非 Windows）
+  /// 规划安装指定 tag 的发布；版本目录名取 version.txt 的内容，没有该资产时使用 tag
+  pub async fn plan_download_tag(&self, client: &reqwest::Client, tag: &str) -> Result<DownloadPlan> {
+    if !valid_version_name(tag) {
+      return Err(anyhow!("无效的版本标签: {}", tag));
+    }
+    let (rel, version, mirror) = self.resolve_release(client, ReleaseQuery::Tag(tag)).await?;
+    plan_for_release(&rel, version, mirror)
+  }
+
+  /// 下载中的资产文件：<cores_dir>/<asset_name>.part，中断后据此续传
+  pub fn part_path(&self, asset_name: &str) -> PathBuf {
+    self.cores_dir.join(format!("{}.part", asset_name))
+  }
+
+  /// 从已下载的资产文件解压安装到 cores/<version>/；make_current 为 true 时将 current 指向该版本并返回 current 下的路径
+  pub fn install_from_file(&mut self, version: &str, asset_name: &str, file: &Path, make_current: bool) -> Result<PathBuf> {
+    let install_dir = self.cores_dir.join(version);
+    fs::create_dir_all(&install_dir)?;
+    let bin_name = target_bin_name();
+    let reader = io::BufReader::new(fs::File::open(file).with_context(|| format!("打开 {} 失败", file.display()))?);
+    let installed_path = install_asset(reader, asset_name, &install_dir, bin_name)?;
     #[cfg(unix)]
     {
       use std::os::unix::fs::PermissionsExt;
@@ -122,84 +366,133 @@ impl VersionManager {
       perms.set_mode(0o755);
       fs::set_permissions(&installed_path, perms)?;
     }
+    if !make_current {
+      return Ok(installed_path);
+    }
+    self.link_current(version, &install_dir, &installed_path)
+  }
 
-    self.mark_installed(&version, installed_path.clone());
-    Ok(installed_path)
+  /// 列出 cores/ 下已安装的版本，按安装时间倒序。不执行 `mihomo -v`，
+  /// version_output 由调用方在释放锁后通过 probe_installed 填充
+  pub fn list_installed(&self) -> Vec<InstalledVersion> {
+    let current = current_link_version(&self.cores_dir);
+    let mut list: Vec<InstalledVersion> = fs::read_dir(&self.cores_dir)
+      .map(|entries| entries.flatten().collect::<Vec<_>>())
+      .unwrap_or_default()
+      .into_iter()
+      .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+      .filter_map(|e| {
+        let version = e.file_name().to_string_lossy().to_string();
+        let bin = installed_binary(&self.cores_dir, &version)?;
+        let meta = e.metadata().ok();
+        let installed_at = meta
+          .and_then(|m| m.created().or_else(|_| m.modified()).ok())
+          .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+          .map(|d| d.as_millis() as u64);
+        Some(InstalledVersion {
+          current: current.as_deref() == Some(version.as_str()),
+          size_bytes: dir_size(&e.path()),
+          installed_at,
+          version_output: None,
+          path: bin.to_string_lossy().to_string(),
+          running: false,
+          version,
+        })
+      })
+      .collect();
+    list.sort_by(|a, b| b.installed_at.cmp(&a.installed_at).then_with(|| b.version.cmp(&a.version)));
+    list
   }
 
-  async fn get_release_and_version(&self, channel: ReleaseChannel) -> Result<(Release, String)> {
-    // 仅使用 MetaCubeX/mihomo
-    const REPO: &str = "MetaCubeX/mihomo";
-    let client = build_gh_client()?;
+  /// 将 current 切换到已安装的版本，返回 current 下的内核路径；已运行的实例在重启后生效
+  pub fn set_current(&mut self, version: &str) -> Result<PathBuf> {
+    let installed_path = self
+      .installed_binary(version)
+      .ok_or_else(|| anyhow!("内核版本 {} 未安装", version))?;
+    let install_dir = self.cores_dir.join(version);
+    self.link_current(version, &install_dir, &installed_path)
+  }
 
-    let rel = match channel {
-      ReleaseChannel::Stable => fetch_latest_release(&client, REPO).await?,
-      ReleaseChannel::Dev => fetch_dev_release(&client, REPO).await?,
-    };
+  /// 删除已安装的版本；current 指向的版本需先切换到其他版本。是否正在运行由调用方检查
+  pub fn remove(&mut self, version: &str) -> Result<()> {
+    if self.installed_binary(version).is_none() {
+      return Err(anyhow!("内核版本 {} 未安装", version));
+    }
+    if current_link_version(&self.cores_dir).as_deref() == Some(version) {
+      return Err(anyhow!("{} 是当前版本，请先切换到其他版本", version));
+    }
+    fs::remove_dir_all(self.cores_dir.join(version)).with_context(|| format!("删除版本 {} 失败", version))?;
+    if self.current_version.as_deref() == Some(version) {
+      self.current_version = None;
+      self.current_core_path = None;
+    }
+    Ok(())
+  }
 
-    let version_txt_asset = rel
-      .assets
-      .iter()
-      .find(|a| a.name.eq_ignore_ascii_case("version.txt"))
-      .cloned()
-      .ok_or_else(|| anyhow!("未找到 version.txt 资产"))?;
+  /// 按保留策略清理旧版本：每个渠道保留最近安装的 keep 个，current、回滚目标与 protected 中的版本始终保留
+  pub fn collect_garbage(&mut self, keep: usize, protected: &HashSet<String>, dry_run: bool) -> GcReport {
+    let mut always: HashSet<String> = protected.clone();
+    always.extend(current_link_version(&self.cores_dir));
+    always.extend(self.current_version.clone());
+    always.extend(self.previous_version());
 
-    let version = fetch_text(&client, &version_txt_asset.browser_download_url, "version.txt")
-      .await?
-      .trim()
-      .to_string();
+    let mut report = GcReport { dry_run, ..Default::default() };
+    let mut seen: HashMap<ReleaseChannel, usize> = HashMap::new();
+    for v in self.list_installed() {
+      let channel = ReleaseChannel::of_version(&v.version);
+      let rank = seen.entry(channel).or_insert(0);
+      *rank += 1;
+      if *rank <= keep || always.contains(&v.version) {
+        report.kept.push(v.version);
+        continue;
+      }
+      if !dry_run {
+        if let Err(e) = fs::remove_dir_all(self.cores_dir.join(&v.version)) {
+          report.failed.push(format!("{}: {}", v.version, e));
+          continue;
+        }
+      }
+      report.freed_bytes += v.size_bytes;
+      report.removed.push(GcEntry { version: v.version, channel, size_bytes: v.size_bytes });
+    }
+    report
+  }
 
-    Ok((rel, version))
+  /// 上一次切换前 current 指向的版本
+  pub fn previous_version(&self) -> Option<String> {
+    let text = fs::read_to_string(self.cores_dir.join(PREVIOUS_FILE)).ok()?;
+    let version = text.trim();
+    valid_version_name(version).then(|| version.to_string())
   }
 
-  pub async fn plan_download(&self, channel: ReleaseChannel) -> Result<DownloadPlan> {
-    let (rel, version) = self.get_release_and_version(channel).await?;
-    let asset = select_target_asset(&rel).context("未找到匹配当前平台与架构的资产")?;
-    let checksum_asset = rel
-      .assets
-      .iter()
-      .find(|a| a.name.to_ascii_lowercase().contains("sha256") || a.name.to_ascii_lowercase().contains("checksum"))
-      .cloned();
-    Ok(DownloadPlan {
-      version,
-      asset_name: asset.name.clone(),
-      asset_url: asset.browser_download_url.clone(),
-      checksum_url: checksum_asset.map(|a| a.browser_download_url),
-    })
+  /// 将 current 切回上一个版本，返回 (回滚前版本, 回滚后版本, current 下的内核路径)
+  pub fn rollback(&mut self) -> Result<(String, String, PathBuf)> {
+    let to = self.previous_version().ok_or_else(|| anyhow!("没有可回滚的版本"))?;
+    if self.installed_binary(&to).is_none() {
+      return Err(anyhow!("上一个版本 {} 已不存在，无法回滚", to));
+    }
+    let from = current_link_version(&self.cores_dir).or_else(|| self.current_version.clone()).unwrap_or_default();
+    let path = self.set_current(&to)?;
+    Ok((from, to, path))
   }
 
-  pub fn install_from_bytes(&mut self, version: &str, asset_name: &str, bytes: &[u8]) -> Result<PathBuf> {
-    let install_dir = self.cores_dir.join(version);
-    fs::create_dir_all(&install_dir)?;
+  #[cfg_attr(not(windows), allow(unused_variables))]
+  fn link_current(&mut self, version: &str, install_dir: &Path, installed_path: &Path) -> Result<PathBuf> {
     let bin_name = target_bin_name();
-    let installed_path = install_asset_bytes(bytes, asset_name, &install_dir, bin_name)?;
-    #[cfg(unix)]
-    {
-      use std::os::unix::fs::PermissionsExt;
-      let mut perms = fs::metadata(&installed_path)?.permissions();
-      perms.set_mode(0o755);
-      fs::set_permissions(&installed_path, perms)?;
+    // 记录切换前的版本，供回滚使用
+    let previous = current_link_version(&self.cores_dir).or_else(|| self.current_version.clone());
+    if let Some(prev) = previous.filter(|p| p != version) {
+      fs::write(self.cores_dir.join(PREVIOUS_FILE), &prev)?;
     }
     // 将 current 指向版本目录，避免复制导致 Linux 上的 setcap 能力丢失
     let current_dir = self.cores_dir.join("current");
+    // 删除现有的 current，否则重新链接失败后复制会经由旧链接覆盖上一版本的内核
+    remove_current(&current_dir).with_context(|| format!("删除 {} 失败", current_dir.display()))?;
 
     #[cfg(unix)]
     {
       use std::os::unix::fs::symlink as symlink_dir;
-      // 删除现有的 current（可能是符号链接或目录）
-      match fs::symlink_metadata(&current_dir) {
-        Ok(meta) => {
-          if meta.file_type().is_symlink() {
-            let _ = fs::remove_file(&current_dir);
-          } else if meta.is_dir() {
-            let _ = fs::remove_dir_all(&current_dir);
-          } else {
-            let _ = fs::remove_file(&current_dir);
-          }
-        }
-        Err(_) => {}
-      }
-      symlink_dir(&install_dir, &current_dir)?;
+      symlink_dir(install_dir, &current_dir)?;
     }
 
     #[cfg(windows)]
@@ -210,14 +503,14 @@ impl VersionManager {
       let mut linked = false;
       #[cfg(windows)]
       {
-        if win_symlink_dir(&install_dir, &current_dir).is_ok() {
+        if win_symlink_dir(install_dir, &current_dir).is_ok() {
           linked = true;
         }
       }
       if !linked {
         fs::create_dir_all(&current_dir)?;
         let current_path = current_dir.join(bin_name);
-        fs::copy(&installed_path, &current_path)?;
+        fs::copy(installed_path, &current_path)?;
       }
     }
 
@@ -225,62 +518,231 @@ impl VersionManager {
     self.mark_installed(version, current_path.clone());
     Ok(current_path)
   }
+
+  pub fn installed_binary(&self, version: &str) -> Option<PathBuf> {
+    installed_binary(&self.cores_dir, version)
+  }
 }
 
-pub(crate) fn build_gh_client() -> Result<reqwest::Client> {
-  let mut headers = reqwest::header::HeaderMap::new();
-  headers.insert(
-    reqwest::header::ACCEPT,
-    reqwest::header::HeaderValue::from_static("application/vnd.github+json"),
-  );
-  headers.insert(
-    reqwest::header::USER_AGENT,
-    reqwest::header::HeaderValue::from_static("mihomo-gui/0.1"),
-  );
+/// 版本号与资产名来自镜像返回的内容，分别用作 cores/ 下的目录名与 part 文件名，需先校验
+fn plan_for_release(rel: &Release, version: String, mirror: Mirror) -> Result<DownloadPlan> {
+  if !valid_version_name(&version) {
+    return Err(anyhow!("无效的版本号: {}", version));
+  }
+  let asset = select_target_asset(rel).context("未找到匹配当前平台与架构的资产")?;
+  if !valid_version_name(&asset.name) {
+    return Err(anyhow!("无效的资产名: {}", asset.name));
+  }
+  let checksum_asset = rel
+    .assets
+    .iter()
+    .find(|a| a.name.to_ascii_lowercase().contains("sha256") || a.name.to_ascii_lowercase().contains("checksum"))
+    .cloned();
+  Ok(DownloadPlan {
+    version,
+    asset_name: asset.name.clone(),
+    asset_url: asset.browser_download_url.clone(),
+    checksum_url: checksum_asset.map(|a| a.browser_download_url),
+    mirror,
+  })
+}
 
-  if let Ok(token) = env::var("GITHUB_TOKEN") {
-    let val = format!("Bearer {}", token);
-    if let Ok(hv) = reqwest::header::HeaderValue::from_str(&val) {
-      headers.insert(reqwest::header::AUTHORIZATION, hv);
-    }
+/// 已安装版本的内核可执行文件：<cores_dir>/<version>/.../mihomo；未安装或名称非法时返回 None
+pub fn installed_binary(cores_dir: &Path, version: &str) -> Option<PathBuf> {
+  if !valid_version_name(version) {
+    return None;
+  }
+  let dir = cores_dir.join(version);
+  let direct = dir.join(target_bin_name());
+  if direct.is_file() {
+    return Some(direct);
   }
+  find_bin_recursive(&dir, target_bin_name())
+}
 
-  let client = reqwest::Client::builder()
-    .default_headers(headers)
-    .build()?;
-  Ok(client)
+/// 可作为 cores/ 下目录名的版本号
+fn valid_version_name(version: &str) -> bool {
+  !(version.is_empty()
+    || version == "current"
+    || version == PREVIOUS_FILE
+    || version.contains(['/', '\\', '?', '#', ':'])
+    || version.contains(char::is_control)
+    || version.starts_with('.'))
 }
 
-async fn fetch_latest_release(client: &reqwest::Client, repo: &str) -> Result<Release> {
-  let url = format!("https://api.github.com/repos/{repo}/releases/latest");
-  let resp = client.get(url).send().await.context("请求 latest release 失败")?;
-  let status = resp.status();
-  if !status.is_success() {
-    let body = resp.text().await.unwrap_or_default();
-    return Err(anyhow!("latest release HTTP 状态错误: {} - {}", status, truncate(&body)));
+/// 删除 current：符号链接只删除链接本身，不影响其指向的版本目录；复制出的目录整体删除
+fn remove_current(current_dir: &Path) -> io::Result<()> {
+  match fs::symlink_metadata(current_dir) {
+    // Windows 上的目录符号链接需以 remove_dir 删除
+    Ok(meta) if meta.file_type().is_symlink() => fs::remove_file(current_dir).or_else(|_| fs::remove_dir(current_dir)),
+    Ok(meta) if meta.is_dir() => fs::remove_dir_all(current_dir),
+    Ok(_) => fs::remove_file(current_dir),
+    Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+    Err(e) => Err(e),
+  }
+}
+
+/// 默认内核的路径：<cores_dir>/current/mihomo
+pub fn current_core_path(cores_dir: &Path) -> PathBuf {
+  cores_dir.join("current").join(target_bin_name())
+}
+
+/// cores/current 当前指向的版本名
+pub fn current_link_version(cores_dir: &Path) -> Option<String> {
+  let target = fs::read_link(cores_dir.join("current")).ok()?;
+  target.file_name().map(|n| n.to_string_lossy().to_string())
+}
+
+/// 内核文件所属的版本名：位于 <cores_dir>/<version>/ 下时返回 version；路径需已解析符号链接
+pub fn version_of_binary(cores_dir: &Path, binary: &Path) -> Option<String> {
+  let root = fs::canonicalize(cores_dir).ok()?;
+  let rel = binary.strip_prefix(&root).ok()?;
+  let first = rel.components().next()?.as_os_str().to_string_lossy().to_string();
+  (first != "current" && rel.components().count() > 1).then_some(first)
+}
+
+/// 为已安装版本逐个执行 `mihomo -v` 填充 version_output；会阻塞，需在锁外通过 spawn_blocking 调用
+pub fn probe_installed(list: &mut [InstalledVersion]) {
+  for v in list.iter_mut() {
+    v.version_output = probe_version_output(Path::new(&v.path));
   }
-  Ok(resp.json::<Release>().await.context("解析 latest release JSON 失败")?)
 }
 
-async fn fetch_dev_release(client: &reqwest::Client, repo: &str) -> Result<Release> {
-  let url = format!("https://api.github.com/repos/{repo}/releases?per_page=10");
-  let resp = client.get(url).send().await.context("请求 releases 列表失败")?;
+/// 执行 `mihomo -v` 验证内核可运行，返回输出首行；会阻塞，调用方不应持有 VersionManager 的锁
+pub fn verify_binary(binary: &Path) -> Result<String> {
+  probe_version_output(binary).ok_or_else(|| anyhow!("{} -v 执行失败", binary.display()))
+}
+
+/// 执行 `<binary> -v` 并返回输出的首行
+pub fn probe_version_output(binary: &Path) -> Option<String> {
+  let out = std::process::Command::new(binary).arg("-v").stdin(std::process::Stdio::null()).output().ok()?;
+  if !out.status.success() {
+    return None;
+  }
+  String::from_utf8_lossy(&out.stdout).lines().map(str::trim).find(|l| !l.is_empty()).map(str::to_string)
+}
+
+fn dir_size(dir: &Path) -> u64 {
+  let Ok(entries) = fs::read_dir(dir) else { return 0 };
+  entries
+    .flatten()
+    .map(|e| match e.file_type() {
+      Ok(t) if t.is_dir() => dir_size(&e.path()),
+      Ok(t) if t.is_file() => e.metadata().map(|m| m.len()).unwrap_or(0),
+      _ => 0,
+    })
+    .sum()
+}
+
+fn api_root(base: &str) -> &str {
+  base.trim_end_matches('/')
+}
+
+/// 请求 releases API；GITHUB_TOKEN 只在直连时发送，避免泄露给代理与第三方服务
+async fn api_get(client: &reqwest::Client, mirror: &Mirror, url: String, what: &str) -> Result<reqwest::Response> {
+  let mut req = client.get(url);
+  if *mirror == Mirror::Direct {
+    if let Ok(token) = env::var("GITHUB_TOKEN") {
+      req = req.bearer_auth(token);
+    }
+  }
+  let resp = req.send().await.with_context(|| format!("请求 {what} 失败"))?;
   let status = resp.status();
   if !status.is_success() {
     let body = resp.text().await.unwrap_or_default();
-    return Err(anyhow!("releases 列表 HTTP 状态错误: {} - {}", status, truncate(&body)));
-  }
-  let list = resp
-    .json::<Vec<Release>>()
-    .await
-    .context("解析 releases 列表 JSON 失败")?;
-  if list.is_empty() {
-    return Err(anyhow!("未找到任何 release"));
+    return Err(anyhow!("{what} HTTP 状态错误: {} - {}", status, truncate(&body)));
   }
-  if let Some(pre) = list.iter().find(|r| r.prerelease) {
-    Ok(pre.clone())
-  } else {
-    Ok(list.into_iter().next().unwrap())
+  Ok(resp)
+}
+
+/// 通过单个镜像查找发布并读取版本号；按 tag 查找且没有 version.txt 时以 tag 作为版本号
+async fn resolve_from(
+  client: &reqwest::Client,
+  mirror: &Mirror,
+  api_base: &str,
+  repo: &str,
+  query: ReleaseQuery<'_>,
+) -> Result<(Release, String)> {
+  let rel = match mirror.api_base(api_base) {
+    Some(base) => {
+      let base = api_root(&base);
+      match query {
+        ReleaseQuery::Latest => {
+          let url = format!("{base}/repos/{repo}/releases/latest");
+          api_get(client, mirror, url, "latest release").await?.json::<Release>().await.context("解析 latest release JSON 失败")?
+        }
+        ReleaseQuery::Dev => {
+          let url = format!("{base}/repos/{repo}/releases?per_page=10");
+          let list = api_get(client, mirror, url, "releases 列表").await?.json::<Vec<Release>>().await.context("解析 releases 列表 JSON 失败")?;
+          pick_dev(list).ok_or_else(|| anyhow!("未找到任何 release"))?
+        }
+        ReleaseQuery::Tag(tag) => {
+          let url = format!("{base}/repos/{repo}/releases/tags/{tag}");
+          api_get(client, mirror, url, &format!("release {tag}")).await?.json::<Release>().await.with_context(|| format!("解析 release {tag} JSON 失败"))?
+        }
+      }
+    }
+    None => {
+      let list = fetch_index(client, mirror).await?;
+      match query {
+        ReleaseQuery::Latest => list.into_iter().find(|r| !r.prerelease).ok_or_else(|| anyhow!("索引中没有稳定版发布"))?,
+        ReleaseQuery::Dev => pick_dev(list).ok_or_else(|| anyhow!("索引中没有任何发布"))?,
+        ReleaseQuery::Tag(tag) => list.into_iter().find(|r| r.tag_name == tag).ok_or_else(|| anyhow!("未找到版本 {}", tag))?,
+      }
+    }
+  };
+  let version_txt = rel.assets.iter().find(|a| a.name.eq_ignore_ascii_case("version.txt"));
+  let version = match (version_txt, query) {
+    (Some(a), _) => fetch_text(client, &mirror.rewrite(&a.browser_download_url), "version.txt").await?.trim().to_string(),
+    (None, ReleaseQuery::Tag(tag)) => tag.to_string(),
+    (None, _) => return Err(anyhow!("未找到 version.txt 资产")),
+  };
+  Ok((rel, version))
+}
+
+/// 通过单个镜像获取一页发布，返回 (发布, 是否还有下一页)
+async fn fetch_release_page(
+  client: &reqwest::Client,
+  mirror: &Mirror,
+  api_base: &str,
+  repo: &str,
+  page: u32,
+  per_page: u32,
+) -> Result<(Vec<Release>, bool)> {
+  let Some(base) = mirror.api_base(api_base) else {
+    let list = fetch_index(client, mirror).await?;
+    // page 来自前端，按 usize 饱和计算，超出范围时得到空页
+    let start = (page.saturating_sub(1) as usize).saturating_mul(per_page as usize);
+    let end = start.saturating_add(per_page as usize).min(list.len());
+    let has_more = end < list.len();
+    return Ok((list.into_iter().skip(start).take(end.saturating_sub(start)).collect(), has_more));
+  };
+  let url = format!("{}/repos/{}/releases?per_page={}&page={}", api_root(&base), repo, per_page, page);
+  let resp = api_get(client, mirror, url, "releases 列表").await?;
+  // 优先依据 Link 头判断是否还有下一页，没有该头时按本页是否已满推断
+  let next = resp
+    .headers()
+    .get(reqwest::header::LINK)
+    .and_then(|v| v.to_str().ok())
+    .map(|v| v.contains("rel=\"next\""));
+  let list = resp.json::<Vec<Release>>().await.context("解析 releases 列表 JSON 失败")?;
+  let has_more = next.unwrap_or(list.len() as u32 >= per_page);
+  Ok((list, has_more))
+}
+
+async fn fetch_index(client: &reqwest::Client, mirror: &Mirror) -> Result<Vec<Release>> {
+  let Mirror::Index { url } = mirror else {
+    return Err(anyhow!("{} 不是静态索引", mirror.label()));
+  };
+  let resp = api_get(client, mirror, url.clone(), "发布索引").await?;
+  resp.json::<Vec<Release>>().await.context("解析发布索引 JSON 失败")
+}
+
+/// 开发版：最新的预发布，没有时取最新的发布
+fn pick_dev(list: Vec<Release>) -> Option<Release> {
+  match list.iter().position(|r| r.prerelease) {
+    Some(i) => list.into_iter().nth(i),
+    None => list.into_iter().next(),
   }
 }
 
@@ -296,7 +758,10 @@ pub(crate) async fn fetch_text(client: &reqwest::Client, url: &str, what: &str)
 
 fn truncate(s: &str) -> String {
   const MAX: usize = 200;
-  if s.len() <= MAX { s.to_string() } else { format!("{}...", &s[..MAX]) }
+  match s.char_indices().nth(MAX) {
+    Some((end, _)) => format!("{}...", &s[..end]),
+    None => s.to_string(),
+  }
 }
 
 fn target_os_keyword() -> &'static str {
@@ -399,23 +864,9 @@ fn select_target_asset(rel: &Release) -> Option<Asset> {
     .cloned()
 }
 
-async fn fetch_bytes(client: &reqwest::Client, url: &str, what: &str) -> Result<Vec<u8>> {
-  let resp = client.get(url).send().await.with_context(|| format!("下载 {what} 失败"))?;
-  let status = resp.status();
-  if !status.is_success() {
-    let body = resp.text().await.unwrap_or_default();
-    return Err(anyhow!("{what} HTTP 状态错误: {} - {}", status, truncate(&body)));
-  }
-  Ok(resp.bytes().await.with_context(|| format!("读取 {what} 二进制失败"))?.to_vec())
-}
-
-pub(crate) fn verify_sha256(bytes: &[u8], checksum_list: &str) -> Result<()> {
-  use sha2::{Digest, Sha256};
-  let mut hasher = Sha256::new();
-  hasher.update(bytes);
-  let out = hasher.finalize();
-  let hex = out.iter().map(|b| format!("{:02x}", b)).collect::<String>();
-
+/// 以已计算好的十六进制 SHA256 在校验列表中查找匹配项
+pub(crate) fn verify_sha256_hex(hex: &str, checksum_list: &str) -> Result<()> {
+  let hex = hex.to_ascii_lowercase();
   // 常见格式："<sha256>  <filename>" 每行一条
   for line in checksum_list.lines() {
     let t = line.trim();
@@ -429,27 +880,27 @@ pub(crate) fn verify_sha256(bytes: &[u8], checksum_list: &str) -> Result<()> {
   Err(anyhow!("SHA256 未在校验列表中匹配"))
 }
 
-fn install_asset_bytes(bytes: &[u8], asset_name: &str, install_dir: &Path, bin_name: &str) -> Result<PathBuf> {
+fn install_asset<R: Read + Seek>(mut reader: R, asset_name: &str, install_dir: &Path, bin_name: &str) -> Result<PathBuf> {
   let lname = asset_name.to_ascii_lowercase();
   if lname.ends_with(".zip") {
-    extract_zip_and_find(bytes, install_dir, bin_name)
+    extract_zip_and_find(reader, install_dir, bin_name)
   } else if lname.ends_with(".tar.gz") || lname.ends_with(".tgz") {
-    extract_targz_and_find(bytes, install_dir, bin_name)
+    extract_targz_and_find(reader, install_dir, bin_name)
   } else if lname.ends_with(".gz") {
     // 可能是单文件 gzip
     let path = install_dir.join(bin_name);
-    extract_gz_to(bytes, &path)?;
+    extract_gz_to(reader, &path)?;
     Ok(path)
   } else {
     // 认为是未压缩的可执行文件
     let path = install_dir.join(bin_name);
-    fs::write(&path, bytes)?;
+    let mut out = fs::File::create(&path)?;
+    std::io::copy(&mut reader, &mut out)?;
     Ok(path)
   }
 }
 
-fn extract_zip_and_find(bytes: &[u8], install_dir: &Path, bin_name: &str) -> Result<PathBuf> {
-  let reader = Cursor::new(bytes);
+fn extract_zip_and_find<R: Read + Seek>(reader: R, install_dir: &Path, bin_name: &str) -> Result<PathBuf> {
   let mut zip = zip::ZipArchive::new(reader).context("解析 zip 失败")?;
   let mut found: Option<PathBuf> = None;
   for i in 0..zip.len() {
@@ -469,15 +920,15 @@ fn extract_zip_and_find(bytes: &[u8], install_dir: &Path, bin_name: &str) -> Res
   found.or_else(|| find_bin_recursive(install_dir, bin_name)).ok_or_else(|| anyhow!("未在 zip 中找到可执行文件"))
 }
 
-fn extract_targz_and_find(bytes: &[u8], install_dir: &Path, bin_name: &str) -> Result<PathBuf> {
-  let gz = flate2::read::GzDecoder::new(Cursor::new(bytes));
+fn extract_targz_and_find<R: Read>(reader: R, install_dir: &Path, bin_name: &str) -> Result<PathBuf> {
+  let gz = flate2::read::GzDecoder::new(reader);
   let mut tar = tar::Archive::new(gz);
   tar.unpack(install_dir).context("解包 tar.gz 失败")?;
   find_bin_recursive(install_dir, bin_name).ok_or_else(|| anyhow!("未在 tar.gz 中找到可执行文件"))
 }
 
-fn extract_gz_to(bytes: &[u8], path: &Path) -> Result<()> {
-  let mut gz = flate2::read::GzDecoder::new(Cursor::new(bytes));
+fn extract_gz_to<R: Read>(reader: R, path: &Path) -> Result<()> {
+  let mut gz = flate2::read::GzDecoder::new(reader);
   let mut out = fs::File::create(path)?;
   std::io::copy(&mut gz, &mut out)?;
   Ok(())
@@ -499,7 +950,307 @@ fn find_bin_recursive(root: &Path, bin_name: &str) -> Option<PathBuf> {
   visit(root, bin_name)
 }
 
-fn install_root() -> PathBuf {
-  let base = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
-  base.join("mihomo-gui").join("cores")
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::net::{build_client, ProxyRoute};
+  use crate::core::test_server::{Reply, TestServer};
+
+  const REPO: &str = "owner/mihomo";
+
+  fn client() -> reqwest::Client {
+    build_client(&ProxyRoute::Direct).unwrap()
+  }
+
+  fn manager(api_base: &str, mirrors: Vec<Mirror>) -> VersionManager {
+    VersionManager {
+      cores_dir: env::temp_dir(),
+      current_version: None,
+      current_core_path: None,
+      repo: REPO.into(),
+      api_base: api_base.into(),
+      channel: ReleaseChannel::Stable,
+      mirrors,
+      mirror_health: MirrorHealthTable::default(),
+      auto_rollback: false,
+    }
+  }
+
+  /// 匹配当前平台与架构的资产名
+  fn platform_asset_name(tag: &str) -> String {
+    format!("mihomo-{}-{}-{}.gz", target_os_keyword(), target_arch_keywords()[0], tag)
+  }
+
+  /// GitHub release 对象；base 非空时附带 version.txt 资产
+  fn release(tag: &str, prerelease: bool, assets: &[&str], base: &str) -> serde_json::Value {
+    let mut list: Vec<serde_json::Value> = assets
+      .iter()
+      .map(|name| serde_json::json!({ "name": name, "browser_download_url": format!("{base}/download/{tag}/{name}") }))
+      .collect();
+    if !base.is_empty() {
+      list.push(serde_json::json!({ "name": "version.txt", "browser_download_url": format!("{base}/download/{tag}/version.txt") }));
+    }
+    serde_json::json!({ "tag_name": tag, "name": tag, "published_at": "2026-01-01T00:00:00Z", "prerelease": prerelease, "assets": list })
+  }
+
+  fn json(value: &serde_json::Value) -> Reply {
+    Reply::new(200, value.to_string()).header("Content-Type", "application/json")
+  }
+
+  /// 按 per_page 与 page 查询参数返回 total 个发布的一页，并按 GitHub 的方式附带 Link 头
+  fn paged_api(total: usize) -> TestServer {
+    TestServer::start(move |req| {
+      let query = req.path.split_once('?').map(|(_, q)| q).unwrap_or("");
+      let param = |key: &str| query.split('&').find_map(|kv| kv.strip_prefix(key)?.strip_prefix('=')?.parse::<usize>().ok());
+      let (per_page, page) = (param("per_page").unwrap_or(30), param("page").unwrap_or(1));
+      let start = (page - 1) * per_page;
+      let items: Vec<_> = (start..total.min(start + per_page))
+        .map(|i| release(&format!("v1.{}.0", total - i), false, &[], ""))
+        .collect();
+      let mut reply = json(&serde_json::Value::Array(items));
+      if start + per_page < total {
+        reply = reply.header("Link", &format!("<http://api/releases?per_page={per_page}&page={}>; rel=\"next\"", page + 1));
+      }
+      reply
+    })
+  }
+
+  fn tags(page: &ReleasePage) -> Vec<&str> {
+    page.releases.iter().map(|r| r.tag.as_str()).collect()
+  }
+
+  #[tokio::test]
+  async fn list_releases_requests_the_page_and_follows_link_next() {
+    let server = paged_api(5);
+    let vm = manager(&server.base, vec![]);
+
+    let first = vm.list_releases(&client(), 1, 2).await.unwrap();
+    assert_eq!(tags(&first), ["v1.5.0", "v1.4.0"]);
+    assert!(first.has_more);
+
+    let last = vm.list_releases(&client(), 3, 2).await.unwrap();
+    assert_eq!(tags(&last), ["v1.1.0"]);
+    assert!(!last.has_more);
+
+    let paths: Vec<_> = server.requests().into_iter().map(|r| r.path).collect();
+    assert!(paths.contains(&format!("/repos/{REPO}/releases?per_page=2&page=1")));
+    assert!(paths.contains(&format!("/repos/{REPO}/releases?per_page=2&page=3")));
+  }
+
+  #[tokio::test]
+  async fn list_releases_clamps_page_arguments() {
+    let server = paged_api(3);
+    let vm = manager(&server.base, vec![]);
+    let page = vm.list_releases(&client(), 0, 1000).await.unwrap();
+    assert_eq!((page.page, page.per_page), (1, MAX_RELEASES_PER_PAGE));
+    assert_eq!(page.releases.len(), 3);
+    assert!(!page.has_more);
+    assert_eq!(server.requests()[0].path, format!("/repos/{REPO}/releases?per_page={MAX_RELEASES_PER_PAGE}&page=1"));
+  }
+
+  #[tokio::test]
+  async fn link_header_takes_precedence_over_page_size() {
+    // 本页已满但 Link 头只有 prev：没有下一页
+    let full = TestServer::start(|_| {
+      json(&serde_json::json!([release("v1.2.0", false, &[], ""), release("v1.1.0", false, &[], "")]))
+        .header("Link", "<http://api/releases?page=1>; rel=\"prev\"")
+    });
+    let page = manager(&full.base, vec![]).list_releases(&client(), 2, 2).await.unwrap();
+    assert!(!page.has_more);
+
+    // 没有 Link 头时按本页是否已满推断
+    let no_link = TestServer::start(|_| json(&serde_json::json!([release("v1.2.0", false, &[], ""), release("v1.1.0", false, &[], "")])));
+    assert!(manager(&no_link.base, vec![]).list_releases(&client(), 1, 2).await.unwrap().has_more);
+    assert!(!manager(&no_link.base, vec![]).list_releases(&client(), 1, 3).await.unwrap().has_more);
+  }
+
+  #[tokio::test]
+  async fn index_mirror_is_paged_locally() {
+    let list: Vec<_> = (1..=5).rev().map(|i| release(&format!("v1.{i}.0"), false, &[], "")).collect();
+    let server = TestServer::start(move |_| json(&serde_json::Value::Array(list.clone())));
+    let vm = manager("http://unused.invalid", vec![Mirror::Index { url: server.url("/index.json") }]);
+
+    let second = vm.list_releases(&client(), 2, 2).await.unwrap();
+    assert_eq!(tags(&second), ["v1.3.0", "v1.2.0"]);
+    assert!(second.has_more);
+
+    let third = vm.list_releases(&client(), 3, 2).await.unwrap();
+    assert_eq!(tags(&third), ["v1.1.0"]);
+    assert!(!third.has_more);
+
+    for page in [9, u32::MAX] {
+      let beyond = vm.list_releases(&client(), page, MAX_RELEASES_PER_PAGE).await.unwrap();
+      assert!(beyond.releases.is_empty());
+      assert!(!beyond.has_more);
+    }
+  }
+
+  #[tokio::test]
+  async fn release_summary_reports_missing_platform_asset() {
+    let ours = platform_asset_name("v1.2.0");
+    let listing = serde_json::json!([
+      release("v1.2.0", false, &[ours.as_str(), "mihomo-plan9-mips-v1.2.0.gz"], ""),
+      release("v1.1.0", false, &["mihomo-plan9-mips-v1.1.0.gz", "checksums.txt"], ""),
+    ]);
+    let server = TestServer::start(move |_| json(&listing));
+    let page = manager(&server.base, vec![]).list_releases(&client(), 1, 10).await.unwrap();
+    assert_eq!(page.releases[0].platform_asset.as_deref(), Some(ours.as_str()));
+    assert_eq!(page.releases[1].platform_asset, None);
+  }
+
+  #[tokio::test]
+  async fn plan_download_fails_without_platform_asset() {
+    let server = TestServer::start(|req| {
+      if req.path.ends_with("/version.txt") {
+        return Reply::new(200, "v1.1.0\n");
+      }
+      let base = format!("http://{}", req.header("host").unwrap());
+      json(&release("v1.1.0", false, &["mihomo-plan9-mips-v1.1.0.gz"], &base))
+    });
+    let err = manager(&server.base, vec![]).plan_download(&client(), ReleaseChannel::Stable).await.unwrap_err();
+    assert!(format!("{:#}", err).contains("未找到匹配当前平台与架构的资产"), "{:#}", err);
+  }
+
+  /// 按渠道取发布：稳定版跳过预发布，开发版取最新的预发布
+  #[tokio::test]
+  async fn channel_selects_prerelease_only_for_dev() {
+    let server = TestServer::start(|req| {
+      let base = format!("http://{}", req.header("host").unwrap());
+      if let Some(rest) = req.path.strip_prefix("/download/") {
+        return Reply::new(200, rest.split('/').next().unwrap().to_string());
+      }
+      let pre = release("v1.20.0-alpha", true, &[platform_asset_name("alpha").as_str()], &base);
+      let stable = release("v1.19.0", false, &[platform_asset_name("v1.19.0").as_str()], &base);
+      if req.path.ends_with("/releases/latest") {
+        return json(&stable);
+      }
+      json(&serde_json::json!([pre, stable]))
+    });
+
+    for vm in [manager(&server.base, vec![]), manager("http://unused.invalid", vec![Mirror::Index { url: server.url("/index.json") }])] {
+      let stable = vm.plan_download(&client(), ReleaseChannel::Stable).await.unwrap();
+      assert_eq!(stable.version, "v1.19.0");
+      assert_eq!(stable.asset_name, platform_asset_name("v1.19.0"));
+
+      let dev = vm.plan_download(&client(), ReleaseChannel::Dev).await.unwrap();
+      assert_eq!(dev.version, "v1.20.0-alpha");
+      assert_eq!(dev.asset_name, platform_asset_name("alpha"));
+    }
+  }
+
+  #[tokio::test]
+  async fn dev_falls_back_to_latest_release_without_prerelease() {
+    let server = TestServer::start(|req| {
+      let base = format!("http://{}", req.header("host").unwrap());
+      if req.path.ends_with("/version.txt") {
+        return Reply::new(200, "v1.19.0");
+      }
+      json(&serde_json::json!([
+        release("v1.19.0", false, &[platform_asset_name("v1.19.0").as_str()], &base),
+        release("v1.18.0", false, &[platform_asset_name("v1.18.0").as_str()], &base),
+      ]))
+    });
+    let plan = manager(&server.base, vec![]).plan_download(&client(), ReleaseChannel::Dev).await.unwrap();
+    assert_eq!(plan.asset_name, platform_asset_name("v1.19.0"));
+  }
+
+  #[test]
+  fn download_routes_prefer_planned_then_healthy_mirrors() {
+    let origin = "https://github.com/owner/mihomo/releases/download/v1.19.0/mihomo.gz";
+    let (a, b) = (Mirror::Prefix { prefix: "https://a.example/".into() }, Mirror::Prefix { prefix: "https://b.example/".into() });
+    let api = Mirror::ApiBase { base: "https://gitea.example/api/v1".into() };
+    let vm = manager(DEFAULT_API_BASE, vec![b.clone(), Mirror::Direct, api, a.clone()]);
+    vm.mirror_health.record_failure(&b, "timeout");
+
+    let routes = vm.download_routes(&a, origin);
+    // 规划所用的镜像在前，失败过的 b 排到最后；api 改写后与直连地址相同而被去重
+    let urls: Vec<_> = routes.iter().map(|(_, url)| url.as_str()).collect();
+    assert_eq!(urls, [format!("https://a.example/{origin}"), origin.to_string(), format!("https://b.example/{origin}")]);
+    assert_eq!(routes[1].0, Mirror::Direct);
+  }
+
+  #[tokio::test]
+  async fn plan_download_rejects_unsafe_version_from_mirror() {
+    let server = TestServer::start(|req| {
+      if req.path.ends_with("/version.txt") {
+        return Reply::new(200, "../..\n");
+      }
+      let base = format!("http://{}", req.header("host").unwrap());
+      json(&release("v1.19.0", false, &[platform_asset_name("v1.19.0").as_str()], &base))
+    });
+    let err = manager(&server.base, vec![]).plan_download(&client(), ReleaseChannel::Stable).await.unwrap_err();
+    assert!(err.to_string().contains("无效的版本号"), "{:#}", err);
+  }
+
+  #[test]
+  fn relinking_current_leaves_previous_version_intact() {
+    let cores = env::temp_dir().join(format!("mihomo-gui-cores-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&cores);
+    for v in ["v1.18.0", "v1.19.0"] {
+      fs::create_dir_all(cores.join(v)).unwrap();
+      fs::write(cores.join(v).join(target_bin_name()), v).unwrap();
+    }
+    let mut vm = manager(DEFAULT_API_BASE, vec![]);
+    vm.cores_dir = cores.clone();
+
+    vm.set_current("v1.18.0").unwrap();
+    let path = vm.set_current("v1.19.0").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "v1.19.0");
+    assert_eq!(fs::read_to_string(cores.join("v1.18.0").join(target_bin_name())).unwrap(), "v1.18.0");
+    assert_eq!(vm.previous_version().as_deref(), Some("v1.18.0"));
+
+    vm.rollback().unwrap();
+    assert_eq!(current_link_version(&cores).as_deref(), Some("v1.18.0"));
+    assert_eq!(fs::read_to_string(cores.join("v1.19.0").join(target_bin_name())).unwrap(), "v1.19.0");
+    fs::remove_dir_all(&cores).unwrap();
+  }
+
+  #[test]
+  fn truncate_keeps_multibyte_boundaries() {
+    let body = "错误".repeat(150);
+    let out = truncate(&body);
+    assert_eq!(out.chars().count(), 203);
+    assert!(out.ends_with("..."));
+    assert_eq!(truncate("短"), "短");
+  }
+
+  #[tokio::test]
+  async fn rate_limit_response_is_reported() {
+    let server = TestServer::start(|_| {
+      Reply::new(403, r#"{"message":"API rate limit exceeded for 127.0.0.1."}"#)
+        .header("X-RateLimit-Remaining", "0")
+    });
+    let vm = manager(&server.base, vec![]);
+    let err = format!("{:#}", vm.list_releases(&client(), 1, 10).await.unwrap_err());
+    assert!(err.contains("403"), "{err}");
+    assert!(err.contains("API rate limit exceeded"), "{err}");
+
+    let health = &vm.mirror_status()[0].health;
+    assert_eq!((health.failures, health.consecutive_failures), (1, 1));
+  }
+
+  #[tokio::test]
+  async fn failing_mirror_falls_through_to_next() {
+    let limited = TestServer::start(|_| Reply::new(429, "slow down"));
+    let ok = paged_api(1);
+    let mirrors = vec![Mirror::ApiBase { base: limited.base.clone() }, Mirror::ApiBase { base: ok.base.clone() }];
+    let vm = manager("http://unused.invalid", mirrors);
+
+    let page = vm.list_releases(&client(), 1, 10).await.unwrap();
+    assert_eq!(tags(&page), ["v1.1.0"]);
+    let status = vm.mirror_status();
+    assert_eq!(status[0].health.failures, 1);
+    assert_eq!(status[1].health.successes, 1);
+  }
+
+  #[tokio::test]
+  async fn error_responses_from_every_mirror_are_collected() {
+    let broken = TestServer::start(|_| Reply::new(500, "upstream exploded"));
+    let garbled = TestServer::start(|_| Reply::new(200, "<html>not json</html>"));
+    let mirrors = vec![Mirror::ApiBase { base: broken.base.clone() }, Mirror::ApiBase { base: garbled.base.clone() }];
+    let err = format!("{:#}", manager("http://unused.invalid", mirrors).list_releases(&client(), 1, 10).await.unwrap_err());
+    assert!(err.contains("所有镜像均失败"), "{err}");
+    assert!(err.contains("500") && err.contains("upstream exploded"), "{err}");
+    assert!(err.contains("解析 releases 列表 JSON 失败"), "{err}");
+  }
 }
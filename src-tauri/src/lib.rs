@@ -5,18 +5,27 @@ pub mod commands;
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   use tauri::Manager;
-  use crate::commands::config_commands::{export_config, import_config, load_all_configs, validate_config};
-  use crate::commands::core_commands::{download_install_latest, fetch_latest_version, get_core_install_dir, get_core_status, get_default_core_path, restart_core, start_core, stop_core};
+  use crate::commands::config_commands::{clean_profile_workdir, export_config, get_last_session, get_profile_core_version, get_profile_workdir, get_runtime_overrides, import_config, load_all_configs, preview_runtime_config, set_auto_reload, set_auto_start, set_profile_core_version, set_runtime_overrides, validate_config};
+  use crate::commands::core_commands::{cancel_download, check_port_conflicts, clear_core_logs, download_install_latest, download_install_version, export_core_logs, fetch_latest_version, gc_core_versions, get_active_download, get_core_install_dir, get_core_logs, get_core_resource_usage, get_core_status, get_default_core_path, get_log_file_config, get_mirror_health, get_previous_version, get_restart_policy, list_core_instances, list_core_log_files, list_core_releases, list_installed_versions, purge_core_logs, reload_core_config, remove_core_instance, remove_version, restart_core, rollback_core_version, set_current_version, set_log_file_config, set_restart_policy, start_core, stop_core};
   use crate::commands::system_commands::{check_tun_hint, install_systemd_service, uninstall_systemd_service, systemd_service_status};
-  use crate::core::manager::CoreManager;
+  use crate::core::registry::CoreRegistry;
   use crate::core::version::VersionManager;
+  use crate::commands::settings_commands::{get_settings, update_settings};
   use crate::config::manager::ConfigManager;
+  use crate::config::settings::SettingsStore;
+
+  // 设置文件是各管理器路径与选项的唯一来源，加载时完成旧版本迁移
+  let store = SettingsStore::load(SettingsStore::default_path());
+  let settings = store.settings.clone();
 
   let builder = tauri::Builder::default()
-    .manage(tauri::async_runtime::Mutex::new(CoreManager::default()))
-    .manage(tauri::async_runtime::Mutex::new(VersionManager::new().expect("init version manager")))
-    .manage(tauri::async_runtime::Mutex::new(ConfigManager::new().expect("init config manager")))
-    .setup(|app| {
+    .manage(tauri::async_runtime::Mutex::new(CoreRegistry::default()))
+    .manage(tauri::async_runtime::Mutex::new(VersionManager::new(&settings).expect("init version manager")))
+    .manage(tauri::async_runtime::Mutex::new(ConfigManager::new(&settings).expect("init config manager")))
+    .manage(tauri::async_runtime::Mutex::new(store))
+    .manage(tauri::async_runtime::Mutex::new(crate::core::download::Downloads::default()))
+    .manage(tauri::async_runtime::Mutex::new(crate::core::net::HttpClients::default()))
+    .setup(move |app| {
       if cfg!(debug_assertions) {
         app
           .handle()
@@ -24,15 +33,29 @@ pub fn run() {
       }
       // 启用系统对话框插件（供前端 @tauri-apps/plugin-dialog 使用）
       app.handle().plugin(tauri_plugin_dialog::init())?;
-      // 自动使用默认 core 路径（<data_dir>/mihomo-gui/cores/current/mihomo）
-      let core_state = app.state::<tauri::async_runtime::Mutex<CoreManager>>();
-      let vm_state = app.state::<tauri::async_runtime::Mutex<VersionManager>>();
-      let vm = tauri::async_runtime::block_on(vm_state.lock());
-      let bin = if cfg!(target_os = "windows") { "mihomo.exe" } else { "mihomo" };
-      let p = vm.cores_dir.join("current").join(bin);
-      if p.exists() {
-        let mut cm = tauri::async_runtime::block_on(core_state.lock());
-        cm.set_core_path(p);
+      // 自动使用默认 core 路径（<data_dir>/cores/current/mihomo）
+      let registry_state = app.state::<tauri::async_runtime::Mutex<CoreRegistry>>();
+      let p = crate::core::version::current_core_path(&settings.paths.cores_dir());
+      // 各实例的 pid 文件与日志目录位于数据根目录下；配置时会清理上次异常退出时遗留的内核进程（避免端口被占用）
+      tauri::async_runtime::block_on(async {
+        registry_state.lock().await.configure(&settings, p.exists().then_some(p), app.handle().clone()).await;
+      });
+      // 后台监督内核进程：退出检测、自动重启与状态事件推送
+      crate::core::supervisor::spawn(app.handle().clone());
+      crate::core::resources::spawn(app.handle().clone());
+      // 监视运行中的配置文件，变化后校验并自动重载
+      crate::config::watcher::spawn(app.handle().clone());
+      // 上次退出时内核在运行且开启了自动运行：延迟后以上次的 profile 启动
+      if let Some(profile) = settings.session.auto_start_profile().map(|p| p.to_path_buf()) {
+        let handle = app.handle().clone();
+        let delay = settings.session.auto_start_delay_ms;
+        tauri::async_runtime::spawn(async move {
+          tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+          log::info!("auto starting core with {}", profile.display());
+          if let Err(e) = crate::commands::core_commands::start_profile(&handle, None, profile, false).await {
+            log::error!("auto start failed: {:?}", e);
+          }
+        });
       }
       Ok(())
     });
@@ -43,10 +66,35 @@ pub fn run() {
       start_core,
       stop_core,
       restart_core,
+      reload_core_config,
+      check_port_conflicts,
       get_core_status,
+      list_core_instances,
+      remove_core_instance,
+      get_restart_policy,
+      set_restart_policy,
+      get_core_logs,
+      clear_core_logs,
+      get_core_resource_usage,
+      list_core_log_files,
+      export_core_logs,
+      purge_core_logs,
+      get_log_file_config,
+      set_log_file_config,
       fetch_latest_version,
       download_install_latest,
+      list_core_releases,
+      get_mirror_health,
+      download_install_version,
+      cancel_download,
+      get_active_download,
       get_core_install_dir,
+      list_installed_versions,
+      set_current_version,
+      remove_version,
+      rollback_core_version,
+      get_previous_version,
+      gc_core_versions,
       get_default_core_path,
       // system
       check_tun_hint,
@@ -58,7 +106,36 @@ pub fn run() {
       validate_config,
       import_config,
       export_config,
+      get_profile_workdir,
+      clean_profile_workdir,
+      get_runtime_overrides,
+      set_runtime_overrides,
+      preview_runtime_config,
+      set_auto_reload,
+      get_profile_core_version,
+      set_profile_core_version,
+      get_last_session,
+      set_auto_start,
+      // settings
+      get_settings,
+      update_settings,
     ])
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application")
+    .run(|app, event| {
+      // 应用退出时优雅停止内核，避免残留进程占用端口
+      if let tauri::RunEvent::Exit = event {
+        let registry_state = app.state::<tauri::async_runtime::Mutex<CoreRegistry>>();
+        let cores = tauri::async_runtime::block_on(registry_state.lock()).all();
+        for core in cores {
+          let mut cm = tauri::async_runtime::block_on(core.lock());
+          if cm.child.is_some() {
+            match tauri::async_runtime::block_on(cm.stop()) {
+              Ok(report) => log::info!("core {} stopped on exit via {:?}", cm.instance, report.method),
+              Err(e) => log::error!("failed to stop core {} on exit: {}", cm.instance, e),
+            }
+          }
+        }
+      }
+    });
 }